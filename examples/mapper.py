@@ -4,7 +4,7 @@ import ctypes
 import os
 import json
 from pathlib import Path
-from ctypes import c_char_p, c_void_p, c_uint8, c_uint32, CFUNCTYPE
+from ctypes import c_char_p, c_void_p, c_uint8, c_uint32, c_int32, CFUNCTYPE
 
 
 dll_relative_path = "../target/release/glr_dylib.dll"
@@ -27,17 +27,31 @@ CALLBACK_TYPE = CFUNCTYPE(None, ctypes.c_void_p, ctypes.c_char_p)
 
 # 3. Define Rust function signatures
 
-# void start_listener(const char* file_path)
+# int32_t start_listener(const char* file_path)
 lib.start_listener.argtypes = [c_char_p]
-lib.start_listener.restype = None
+lib.start_listener.restype = c_int32
 
-# void add_callback(uint8_t code, uint8_t message_type, uint32_t channel_id, void* context, void* callback)
+# int32_t add_callback(uint8_t code, uint8_t message_type, uint32_t channel_id, void* context, void* callback)
 lib.add_callback.argtypes = [c_uint8, c_uint8, c_uint32, c_void_p, c_void_p]
-lib.add_callback.restype = None
+lib.add_callback.restype = c_int32
 
-# void remove_callback(uint32_t channel_id)
+# int32_t remove_callback(uint8_t code, uint32_t channel_id)
 lib.remove_callback.argtypes = [c_uint8, c_uint32]
-lib.remove_callback.restype = None
+lib.remove_callback.restype = c_int32
+
+# int32_t glr_last_error(char* buf, uint32_t len)
+lib.glr_last_error.argtypes = [c_char_p, c_uint32]
+lib.glr_last_error.restype = c_int32
+
+GLR_OK = 0
+
+def check(status):
+    """raises with the message of glr_last_error when a function returned an error"""
+    if status < GLR_OK:
+        buf = ctypes.create_string_buffer(512)
+        lib.glr_last_error(buf, len(buf))
+        raise RuntimeError(f"glr_dylib error {status}: {buf.value.decode('utf-8', 'replace')}")
+    return status
 
 #
 # THIS IS WHERE THE ACTUAL CODE STARTS
@@ -64,10 +78,10 @@ msg_type = 1      # e.g., SubscriptionType::JSON
 channel_id = 1    # your app-defined channel ID
 callback_fn_ptr = ctypes.cast(my_event_callback, c_void_p)
 
-lib.add_callback(code, msg_type, channel_id, 0, callback_fn_ptr)
+check(lib.add_callback(code, msg_type, channel_id, 0, callback_fn_ptr))
 
 # Start the listener thread
-lib.start_listener(log_folder_path.encode('utf-8'))
+check(lib.start_listener(log_folder_path.encode('utf-8')))
 
 import time
 print("Waiting for callbacks from .dll")
@@ -4,7 +4,7 @@ import ctypes
 import os
 import json
 from pathlib import Path
-from ctypes import POINTER, c_char_p, c_void_p, c_uint8, c_uint32, CFUNCTYPE
+from ctypes import POINTER, c_char_p, c_void_p, c_uint8, c_uint32, c_int32, CFUNCTYPE
 from tkinter import Tk, filedialog
 
 Tk().withdraw()  # Hide the root window
@@ -29,9 +29,23 @@ CALLBACK_TYPE = CFUNCTYPE(None, ctypes.c_void_p, ctypes.c_char_p)
 
 # 3. Define Rust function signatures
 
-# void process_paths(paths: *const *const c_char, len: uint_32, code: uint_8, message_type: uint_8, context: *const c_void, event_callback_ptr: *const c_void)
+# int32_t process_paths(paths: *const *const c_char, len: uint_32, code: uint_8, message_type: uint_8, context: *const c_void, event_callback_ptr: *const c_void)
 lib.process_paths.argtypes = [POINTER(c_char_p), c_uint32, c_uint8, c_uint8, c_void_p, c_void_p]
-lib.process_paths.restype = None
+lib.process_paths.restype = c_int32
+
+# int32_t glr_last_error(char* buf, uint32_t len)
+lib.glr_last_error.argtypes = [c_char_p, c_uint32]
+lib.glr_last_error.restype = c_int32
+
+GLR_OK = 0
+
+def check(status):
+    """raises with the message of glr_last_error when a function returned an error"""
+    if status < GLR_OK:
+        buf = ctypes.create_string_buffer(512)
+        lib.glr_last_error(buf, len(buf))
+        raise RuntimeError(f"glr_dylib error {status}: {buf.value.decode('utf-8', 'replace')}")
+    return status
 
 #
 # THIS IS WHERE THE ACTUAL CODE STARTS
@@ -62,4 +76,4 @@ length = c_uint32(len(encoded_paths))
 code = 2          # e.g., SubscribeCode::Tokenizer
 msg_type = 1      # e.g., SubscriptionType::JSON
 
-lib.process_paths(c_paths, length, code, msg_type, 0, callback_fn_ptr)
+check(lib.process_paths(c_paths, length, code, msg_type, 0, callback_fn_ptr))
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use num_enum::{FromPrimitive, IntoPrimitive};
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,7 @@ use crate::location::Location;
     FromPrimitive,
     IntoPrimitive,
     strum::IntoStaticStr,
+    strum::EnumString,
     Serialize,
     Deserialize,
     Hash,
@@ -80,6 +81,34 @@ impl Display for LevelDescriptor {
     }
 }
 
+/// parses the same format `Display` outputs, e.g. `R4E1`, `OG.R2A1` or `TRAINING`
+impl FromStr for LevelDescriptor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_ascii_uppercase();
+        if upper == "TRAINING" {
+            return Ok(LevelDescriptor::new(Rundown::TRAINING, 0, 0));
+        }
+
+        let bytes = upper.as_bytes();
+        if bytes.len() < 3 {
+            return Err(format!("'{s}' is not a level name"));
+        }
+
+        let (tier, level) = (bytes[bytes.len() - 2], bytes[bytes.len() - 1]);
+        if !tier.is_ascii_uppercase() || !(b'1'..=b'9').contains(&level) {
+            return Err(format!("'{s}' is not a level name"));
+        }
+
+        let rundown = upper[..upper.len() - 2]
+            .parse::<Rundown>()
+            .map_err(|_| format!("Unknown rundown in level name '{s}'"))?;
+
+        Ok(LevelDescriptor::new(rundown, tier - b'A', level - b'1'))
+    }
+}
+
 #[derive(Default, Debug, PartialEq, Eq, strum::IntoStaticStr, Serialize, Deserialize, Clone)]
 enum KeyColor {
     PURPLE,
@@ -152,20 +181,87 @@ impl Display for KeyDescriptor {
 pub enum ObjectiveFunction {
     #[strum(to_string = "HSU")]
     HSU_FindTakeSample,
+    #[strum(to_string = "HSU Activate")]
+    HSU_ActivateSmall,
     #[strum(to_string = "Uplink")]
     TerminalUplink,
+    #[strum(to_string = "Corrupted Uplink")]
+    CorruptedTerminalUplink,
     #[strum(to_string = "Command")]
     SpecialTerminalCommand,
+    #[strum(to_string = "Gather Terminal")]
+    GatherTerminal,
+    #[strum(to_string = "Timed Sequence")]
+    TimedTerminalSequence,
+    #[strum(to_string = "Reactor Startup")]
+    Reactor_Startup,
+    #[strum(to_string = "Reactor Shutdown")]
+    Reactor_Shutdown,
+    #[strum(to_string = "Generator")]
+    PowerCellDistribution,
+    #[strum(to_string = "Generator Cluster")]
+    CentralGeneratorCluster,
     Unknown,
 }
 
+impl ObjectiveFunction {
+    pub fn into_location(&self, zone: u64, id: u64) -> Location {
+        match self {
+            ObjectiveFunction::TerminalUplink
+            | ObjectiveFunction::CorruptedTerminalUplink
+            | ObjectiveFunction::SpecialTerminalCommand
+            | ObjectiveFunction::GatherTerminal
+            | ObjectiveFunction::TimedTerminalSequence => {
+                Location::Terminal(self.clone(), zone, id)
+            }
+            ObjectiveFunction::Reactor_Startup | ObjectiveFunction::Reactor_Shutdown => {
+                Location::Reactor(self.clone(), zone, id)
+            }
+            ObjectiveFunction::PowerCellDistribution => Location::Generator(zone, id),
+            ObjectiveFunction::CentralGeneratorCluster => Location::GeneratorCluster(zone, id),
+            ObjectiveFunction::HSU_FindTakeSample
+            | ObjectiveFunction::HSU_ActivateSmall
+            | ObjectiveFunction::Unknown => {
+                Location::BigObjective(Into::<&str>::into(self).to_owned(), zone, id)
+            }
+        }
+    }
+}
+
 impl From<&str> for ObjectiveFunction {
     fn from(value: &str) -> Self {
         match value {
             "HSU_FindTakeSample" => ObjectiveFunction::HSU_FindTakeSample,
+            "HSU_ActivateSmall" => ObjectiveFunction::HSU_ActivateSmall,
             "TerminalUplink" => ObjectiveFunction::TerminalUplink,
+            "CorruptedTerminalUplink" => ObjectiveFunction::CorruptedTerminalUplink,
             "SpecialTerminalCommand" => ObjectiveFunction::SpecialTerminalCommand,
+            "GatherTerminal" => ObjectiveFunction::GatherTerminal,
+            "TimedTerminalSequence" => ObjectiveFunction::TimedTerminalSequence,
+            "Reactor_Startup" => ObjectiveFunction::Reactor_Startup,
+            "Reactor_Shutdown" => ObjectiveFunction::Reactor_Shutdown,
+            "PowerCellDistribution" => ObjectiveFunction::PowerCellDistribution,
+            "CentralGeneratorCluster" => ObjectiveFunction::CentralGeneratorCluster,
             _ => ObjectiveFunction::Unknown,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_descriptor_from_str() {
+        for name in ["R4E1", "R1A1", "R7C3", "OG.R2B4", "TRAINING"] {
+            let level: LevelDescriptor = name.parse().unwrap();
+
+            assert_eq!(level.to_string(), name);
+        }
+
+        assert_eq!("r8e2".parse::<LevelDescriptor>().unwrap().to_string(), "R8E2");
+        assert!("R4".parse::<LevelDescriptor>().is_err());
+        assert!("R9A1".parse::<LevelDescriptor>().is_err());
+        assert!("R4E0".parse::<LevelDescriptor>().is_err());
+    }
+}
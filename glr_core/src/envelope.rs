@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::time::Time;
+
+/// bumped whenever a field of [`Envelope`] is added, removed or changes meaning.
+/// It does not follow the bitdata schema version, which only covers the binary layout.
+pub const ENVELOPE_SCHEMA_VERSION: u32 = 1;
+
+/// optional wrapper around every message of a subscription, see the README
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    /// the version of the envelope fields, [`ENVELOPE_SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// the subscribe code of the subscription
+    pub code: u8,
+    /// counts the messages of this subscription from 0, a gap means a message was dropped
+    pub sequence: u64,
+    /// counts every output of the DLL, the same output sent to multiple
+    /// subscriptions has the same number so it can order them across channels
+    pub global_sequence: u64,
+    /// the time of the log line that caused the output
+    pub log_time: Time,
+    /// when the output was sent
+    pub emitted_at: DateTime<Utc>,
+    /// the log file that was being read, if any
+    pub source: Option<String>,
+    pub payload: T,
+}
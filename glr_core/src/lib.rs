@@ -1,4 +1,5 @@
 pub mod data;
+pub mod envelope;
 pub mod location;
 pub mod time;
 pub mod token;
@@ -3,6 +3,8 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
 
+use crate::data::ObjectiveFunction;
+
 /// taken from https://github.com/Angry-Maid/rusted-mapper
 #[derive(
     FromRepr,
@@ -39,6 +41,15 @@ pub enum ItemIdentifier {
     Unknown(u8),
 }
 
+impl ItemIdentifier {
+    /// big items that are carried around and do not get a seed in the logs. Only
+    /// cryos and cargos are known to be logged like this, the others wait for
+    /// their seed like the small items.
+    pub fn is_big_pickup(&self) -> bool {
+        matches!(self, ItemIdentifier::Cryo | ItemIdentifier::Cargo)
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 pub enum Location {
     // name, zone, id
@@ -48,9 +59,21 @@ pub enum Location {
     // gatherable identifier, zone, id
     Gatherable(ItemIdentifier, u64, u64),
 
-    // hsu/terminal/other: name, zone and XX_area
+    // hsu/other: name, zone and XX_area
     BigObjective(String, u64, u64),
 
+    // uplink/command/gather terminals: function, zone and XX_area
+    Terminal(ObjectiveFunction, u64, u64),
+
+    // reactor startup/shutdown: function, zone and XX_area
+    Reactor(ObjectiveFunction, u64, u64),
+
+    // generator for power cell distribution: zone and XX_area
+    Generator(u64, u64),
+
+    // central generator cluster: zone and XX_area
+    GeneratorCluster(u64, u64),
+
     // big collectables (cryo, cargos etc.): only identifier and zone
     BigCollectable(ItemIdentifier, u64),
 
@@ -65,6 +88,10 @@ impl Location {
             | Location::BulkheadKey(_, zone, _)
             | Location::Gatherable(_, zone, _)
             | Location::BigObjective(_, zone, _)
+            | Location::Terminal(_, zone, _)
+            | Location::Reactor(_, zone, _)
+            | Location::Generator(zone, _)
+            | Location::GeneratorCluster(zone, _)
             | Location::BigCollectable(_, zone) => *zone,
             Location::GenerationStarted(_) => 9999,
         }
@@ -82,6 +109,13 @@ impl Display for Location {
             Location::Gatherable(identifier, zone, id) => {
                 write!(f, "{}: ZONE {} at {}", identifier, zone, id)
             }
+            Location::Terminal(function, zone, id) | Location::Reactor(function, zone, id) => {
+                write!(f, "{}: ZONE {} at {}", Into::<&str>::into(function), zone, id)
+            }
+            Location::Generator(zone, id) => write!(f, "Generator: ZONE {} at {}", zone, id),
+            Location::GeneratorCluster(zone, id) => {
+                write!(f, "Generator Cluster: ZONE {} at {}", zone, id)
+            }
             Location::BigCollectable(name, zone) => write!(f, "{}: ZONE {}", name, zone),
             Location::GenerationStarted(_) => write!(f, "-1"),
         }
@@ -100,6 +100,10 @@ where
         self.did_overload
     }
 
+    pub fn get_checkpoint_count(&self) -> u8 {
+        self.used_checkpoint
+    }
+
     pub fn get_is_win(&self) -> bool {
         self.is_win
     }
@@ -1,6 +1,8 @@
-use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(u8)]
 pub enum ResourceType {
     #[default]
@@ -10,7 +12,7 @@ pub enum ResourceType {
     ToolRefillpack,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(u8)]
 pub enum LockState {
     #[default]
@@ -19,17 +21,190 @@ pub enum LockState {
     BreakLock,
 }
 
+/// what an item found by the seed indexer is. Serialized as the name
+/// used before this enum existed, e.g. `KeyZ51` or `ArtifactContainer`.
+///
+/// The typed form is not serialized next to the name: the name parses back
+/// to the same variant (the indexer builds `Objective` items from their name
+/// too, so an objective never has the name of another variant), while adding
+/// it would change the shape of `Key` and the bitdata layout for every reader.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ItemKind {
+    ColoredKey { for_zone: i32 },
+    /// `for_zone` is set when the key opens a bulkhead door of that zone
+    BulkheadKey { for_zone: Option<i32> },
+    Cell,
+    Artifact { container: bool },
+    Consumable { container: bool },
+    Objective(String),
+}
+
+impl Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemKind::ColoredKey { for_zone } => write!(f, "KeyZ{for_zone}"),
+            ItemKind::BulkheadKey { for_zone: Some(zone) } => write!(f, "BulkKeyZ{zone}"),
+            ItemKind::BulkheadKey { for_zone: None } => write!(f, "BulkKey"),
+            ItemKind::Cell => write!(f, "Cell"),
+            ItemKind::Artifact { container: true } => write!(f, "ArtifactContainer"),
+            ItemKind::Artifact { container: false } => write!(f, "ArtifactWorldspawn"),
+            ItemKind::Consumable { container: true } => write!(f, "ConsumableContainer"),
+            ItemKind::Consumable { container: false } => write!(f, "ConsumableWorldspawn"),
+            ItemKind::Objective(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl From<&str> for ItemKind {
+    fn from(value: &str) -> Self {
+        let zone_of = |prefix: &str| value.strip_prefix(prefix)?.parse::<i32>().ok();
+
+        if let Some(for_zone) = zone_of("KeyZ") {
+            return ItemKind::ColoredKey { for_zone };
+        }
+        if let Some(zone) = zone_of("BulkKeyZ") {
+            return ItemKind::BulkheadKey { for_zone: Some(zone) };
+        }
+
+        match value {
+            "BulkKey" => ItemKind::BulkheadKey { for_zone: None },
+            "Cell" => ItemKind::Cell,
+            "ArtifactContainer" => ItemKind::Artifact { container: true },
+            "ArtifactWorldspawn" => ItemKind::Artifact { container: false },
+            "ConsumableContainer" => ItemKind::Consumable { container: true },
+            "ConsumableWorldspawn" => ItemKind::Consumable { container: false },
+            _ => ItemKind::Objective(value.to_owned()),
+        }
+    }
+}
+
+impl Serialize for ItemKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(String::deserialize(deserializer)?.as_str().into())
+    }
+}
+
+/// how much an item or resource pack output by the seed indexer can be trusted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Confidence {
+    /// generated from a level that was checked against the game
+    #[default]
+    Verified,
+    /// generated from a level that is only partially modeled
+    Inferred,
+    /// generated by an objective that copies a bug of the game (`is_bugged`), which is not fully understood
+    BuggedPath,
+    /// there was no spot left for it so the id is -1
+    Overflow,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub confidence: Confidence,
+    /// the part of the level descriptor that spawned it, e.g. `zone L0 D0 Z51 unlocked_by` or `staged_objectives[0](HSU)`
+    pub rule: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum OutputSeedIndexer {
     Seed(f32),
-    Key(String, u8, i32, i32),               // dimension, zone, id
-    ResourcePack(ResourceType, u8, i32, i32, u8), // dimension, zone, id of box, pack size
+    Key(ItemKind, u8, i32, i32, Provenance), // dimension, zone, id
+    ResourcePack(ResourceType, u8, i32, i32, u8, f32, bool, Provenance), // dimension, zone, id of box, pack size, amount, is remainder
     GenerationOverflow(usize),                // how many times the build seed went over in the level
     GenerationOverflowHash([u8; 32]),         // the hash for the generation overflow
+    KnownLayout(String, String),              // name and notes of the layout variant the overflow hash belongs to
     LockStateChange(u8, i32, i32, LockState), // dimension, zone, id
     GenerationEnd,
     GenerationStart(String),
     ZoneGenEnded(u32),
+    ZoneResourceSummary(u8, i32, Vec<ResourceTotal>), // dimension, zone
 
     ProcessFailed,
 }
+
+/// all packs of one resource type with the same lock state in a zone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTotal {
+    pub resource: ResourceType,
+    pub lock: LockState,
+    pub packs: u32,
+    pub uses: u32,
+    pub amount: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SeedStream {
+    Level,
+    Build,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedTraceEntry {
+    pub stream: SeedStream,
+    pub index: usize,
+    pub value: f32,
+    pub label: String,
+    pub layer: Option<u8>,
+    pub dimension: Option<u8>,
+    pub zone: Option<i32>,
+    pub id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum OutputSeedTrace {
+    GenerationStart(String),
+    Value(SeedTraceEntry),
+    GenerationEnd,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_kind_legacy_names() {
+        for name in [
+            "KeyZ51", "BulkKeyZ104", "BulkKey", "Cell", "ArtifactContainer", "ArtifactWorldspawn",
+            "ConsumableContainer", "ConsumableWorldspawn", "ID", "KeyZ",
+        ] {
+            assert_eq!(ItemKind::from(name).to_string(), name);
+        }
+
+        assert_eq!(ItemKind::from("KeyZ51"), ItemKind::ColoredKey { for_zone: 51 });
+        assert_eq!(ItemKind::from("KeyZ"), ItemKind::Objective("KeyZ".to_owned()));
+
+        // the name is enough to get the typed form back
+        for kind in [
+            ItemKind::ColoredKey { for_zone: -3 },
+            ItemKind::BulkheadKey { for_zone: Some(104) },
+            ItemKind::BulkheadKey { for_zone: None },
+            ItemKind::Cell,
+            ItemKind::Artifact { container: true },
+            ItemKind::Artifact { container: false },
+            ItemKind::Consumable { container: true },
+            ItemKind::Consumable { container: false },
+            ItemKind::from("GLP_Tube"),
+        ] {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(serde_json::from_str::<ItemKind>(&json).unwrap(), kind, "{json}");
+        }
+
+        let provenance = Provenance {
+            confidence: Confidence::Verified,
+            rule: "zone L0 D0 Z50 unlocked_by".to_owned(),
+        };
+        let json = serde_json::to_string(&OutputSeedIndexer::Key(ItemKind::Cell, 0, 51, 3, provenance)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Key":["Cell",0,51,3,{"confidence":"Verified","rule":"zone L0 D0 Z50 unlocked_by"}]}"#
+        );
+    }
+}
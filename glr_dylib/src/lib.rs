@@ -1,9 +1,70 @@
 use std::{
     ffi::{c_char, c_void, CStr},
     path::PathBuf,
+    time::Duration,
 };
 
-use glr_lib::dll_exports::structs::{CallbackInfo, EventCallback};
+use glr_core::{data::LevelDescriptor, envelope::ENVELOPE_SCHEMA_VERSION};
+use glr_lib::dll_exports::{
+    bitdata::BITDATA_SCHEMA_VERSION,
+    dispatcher::Delivery,
+    enums::{SubscribeCode, SubscriptionType},
+    error::{GLR_OK, GlrError, last_error, to_status},
+    event_queue::OverflowPolicy,
+    functions::DEFAULT_LISTENER,
+    jobs::{JobProgress, ProgressCallback},
+    structs::{CallbackFn, CallbackInfo},
+};
+
+/// checks `code` and `message_type` and builds the callback they describe
+fn callback_info(
+    code: u8,
+    message_type: u8,
+    channel_id: u32,
+    context: *const c_void,
+    event_callback_ptr: *const c_void,
+) -> Result<CallbackInfo, GlrError> {
+    let code = SubscribeCode::from_u8(code)?;
+    let (message_type, envelope) = SubscriptionType::from_flags(message_type)?;
+    // a callback without a function would be taken as removing the subscription
+    if event_callback_ptr.is_null() {
+        return Err(GlrError::NullPointer("event_callback"));
+    }
+    // binary message types are given to a (context, data, len) function, text ones to (context, message)
+    let event_callback = unsafe { CallbackFn::from_ptr(message_type, event_callback_ptr) };
+
+    Ok(CallbackInfo::new(code, message_type, channel_id, context.into(), event_callback).with_envelope(envelope))
+}
+
+/// the paths in an array of `len` strings, skipping null ones
+fn paths_from_ptr(paths: *const *const c_char, len: u32) -> Result<Vec<PathBuf>, GlrError> {
+    if paths.is_null() {
+        return Err(GlrError::NullPointer("paths"));
+    }
+
+    let slice = unsafe { std::slice::from_raw_parts(paths, len as usize) };
+
+    Ok(slice
+        .iter()
+        .filter_map(|&ptr| {
+            if ptr.is_null() {
+                None
+            } else {
+                let c_str = unsafe { CStr::from_ptr(ptr) };
+                Some(PathBuf::from(c_str.to_string_lossy().into_owned()))
+            }
+        })
+        .collect())
+}
+
+fn path_from_ptr(ptr: *const c_char, name: &'static str) -> Result<PathBuf, GlrError> {
+    if ptr.is_null() {
+        return Err(GlrError::NullPointer(name));
+    }
+
+    let string = unsafe { CStr::from_ptr(ptr) }.to_string_lossy();
+    Ok(PathBuf::from(&*string))
+}
 
 /// starts a folder listener in that file_path. This file_path must
 /// containg GTFO logs that the program will then read and output
@@ -13,20 +74,41 @@ use glr_lib::dll_exports::structs::{CallbackInfo, EventCallback};
 /// if you call this function again prior to deleting
 /// said FolderWatcher it will shutdown the previous on and
 /// all threads connected to it and then start a new one.
+///
+/// Like every function returning a status, it returns `GLR_OK` (0) or a
+/// negative error code, with the message available from `glr_last_error`.
 #[unsafe(no_mangle)]
-pub extern "C" fn start_listener(file_path: *const c_char) {
-    let path = unsafe {
-        if file_path.is_null() {
-            return;
-        }
+pub extern "C" fn start_listener(file_path: *const c_char) -> i32 {
+    let result = path_from_ptr(file_path, "file_path")
+        .and_then(glr_lib::dll_exports::functions::start_listener);
+
+    to_status(result, |_| GLR_OK)
+}
 
-        let c_str = CStr::from_ptr(file_path);
-        let string = c_str.to_string_lossy();
+/// starts another listener reading the logs in `file_path`, independent of
+/// the one `start_listener` uses: it has its own callbacks, queues and parser
+/// state. Returns its id, which is always positive, or a negative status.
+#[unsafe(no_mangle)]
+pub extern "C" fn create_listener(file_path: *const c_char) -> i32 {
+    let result = path_from_ptr(file_path, "file_path")
+        .and_then(glr_lib::dll_exports::functions::create_listener);
 
-        PathBuf::from(&*string)
-    };
+    to_status(result, |listener| listener as i32)
+}
+
+/// changes the folder the listener reads, `start_listener` for listener 0
+#[unsafe(no_mangle)]
+pub extern "C" fn set_listener_folder(listener: u32, file_path: *const c_char) -> i32 {
+    let result = path_from_ptr(file_path, "file_path")
+        .and_then(|path| glr_lib::dll_exports::functions::set_listener_folder(listener, path));
 
-    glr_lib::dll_exports::functions::start_listener(path);
+    to_status(result, |_| GLR_OK)
+}
+
+/// stops the listener, dropping its callbacks and closing its queues
+#[unsafe(no_mangle)]
+pub extern "C" fn destroy_listener(listener: u32) -> i32 {
+    to_status(glr_lib::dll_exports::functions::destroy_listener(listener), |_| GLR_OK)
 }
 
 ///
@@ -37,59 +119,247 @@ pub extern "C" fn add_callback(
     channel_id: u32,
     context: *const c_void,
     event_callback_ptr: *const c_void,
-) {
-    let code = code.into();
-    let message_type = message_type.into();
-    let event_callback = if event_callback_ptr.is_null() {
-        None
-    } else {
-        Some(unsafe {
-            // Cast the void pointer into a function pointer
-            std::mem::transmute::<*const c_void, EventCallback>(event_callback_ptr)
-        })
-    };
+) -> i32 {
+    add_callback_filtered(code, message_type, channel_id, context, event_callback_ptr, std::ptr::null())
+}
+
+/// the same as `add_callback` but only the events named in `filter` (can be
+/// null) are sent, e.g. "BadPacketSentByPlayer" or "!ItemAllocated,!ItemSpawn".
+/// Events that are filtered out are never serialized.
+#[unsafe(no_mangle)]
+pub extern "C" fn add_callback_filtered(
+    code: u8,
+    message_type: u8,
+    channel_id: u32,
+    context: *const c_void,
+    event_callback_ptr: *const c_void,
+    filter: *const c_char,
+) -> i32 {
+    listener_add_callback(DEFAULT_LISTENER, code, message_type, channel_id, context, event_callback_ptr, filter)
+}
 
-    glr_lib::dll_exports::functions::add_callback(CallbackInfo::new(
+/// `add_callback_filtered` on one of the listeners made by `create_listener`
+#[unsafe(no_mangle)]
+pub extern "C" fn listener_add_callback(
+    listener: u32,
+    code: u8,
+    message_type: u8,
+    channel_id: u32,
+    context: *const c_void,
+    event_callback_ptr: *const c_void,
+    filter: *const c_char,
+) -> i32 {
+    let delivery = Delivery::default();
+    listener_add_callback_bounded(
+        listener,
         code,
         message_type,
         channel_id,
-        context.into(),
-        event_callback,
-    ));
+        context,
+        event_callback_ptr,
+        filter,
+        delivery.capacity as u32,
+        delivery.policy.into(),
+    )
+}
+
+/// `listener_add_callback` choosing the queue the callback is given its
+/// messages from: it holds up to `capacity` messages and `overflow_policy`
+/// says what happens when it is full, 0 waits for room (slowing down the
+/// listener), 1 drops the oldest message and 2 the newest one.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn listener_add_callback_bounded(
+    listener: u32,
+    code: u8,
+    message_type: u8,
+    channel_id: u32,
+    context: *const c_void,
+    event_callback_ptr: *const c_void,
+    filter: *const c_char,
+    capacity: u32,
+    overflow_policy: u8,
+) -> i32 {
+    let result = callback_info(code, message_type, channel_id, context, event_callback_ptr).and_then(|callback| {
+        let policy = OverflowPolicy::try_from(overflow_policy)
+            .map_err(|_| GlrError::InvalidOverflowPolicy(overflow_policy))?;
+        let callback = callback.with_delivery(Delivery { capacity: capacity as usize, policy });
+
+        let filter = match filter.is_null() {
+            true => None,
+            false => {
+                let filter = unsafe { CStr::from_ptr(filter) }.to_string_lossy();
+                Some(glr_lib::dll_exports::functions::parse_filter(callback.get_code(), &filter)?)
+            }
+        };
+
+        glr_lib::dll_exports::functions::add_callback(listener, callback.with_filter(filter))
+    });
+
+    to_status(result, |_| GLR_OK)
+}
+
+///
+#[unsafe(no_mangle)]
+pub extern "C" fn remove_callback(code: u8, channel_id: u32) -> i32 {
+    listener_remove_callback(DEFAULT_LISTENER, code, channel_id)
+}
+
+/// writes how many messages the overflow policy of the callback dropped to `dropped`
+///
+/// # Safety
+///
+/// `dropped` must be null or point to a `uint64_t` that can be written.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dropped_events(listener: u32, code: u8, channel_id: u32, dropped: *mut u64) -> i32 {
+    if dropped.is_null() {
+        return GlrError::NullPointer("dropped").into_status();
+    }
+
+    let result = SubscribeCode::from_u8(code)
+        .and_then(|code| glr_lib::dll_exports::functions::dropped_events(listener, code, channel_id))
+        .map(|count| unsafe {
+            *dropped = count;
+        });
+
+    to_status(result, |_| GLR_OK)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn listener_remove_callback(listener: u32, code: u8, channel_id: u32) -> i32 {
+    let result = SubscribeCode::from_u8(code)
+        .and_then(|code| glr_lib::dll_exports::functions::remove_callback(listener, code, channel_id));
+
+    to_status(result, |_| GLR_OK)
+}
+
+/// subscribes a queue to `code` instead of a callback, so the messages can be
+/// taken with `poll_event` on whatever thread you want. `message_type` works
+/// the same as in `add_callback`. Returns the id of the queue, which is always
+/// positive, or a negative status.
+#[unsafe(no_mangle)]
+pub extern "C" fn create_queue(code: u8, message_type: u8) -> i32 {
+    listener_create_queue(DEFAULT_LISTENER, code, message_type)
+}
+
+/// `create_queue` on one of the listeners made by `create_listener`
+#[unsafe(no_mangle)]
+pub extern "C" fn listener_create_queue(listener: u32, code: u8, message_type: u8) -> i32 {
+    let delivery = Delivery::default();
+    listener_create_queue_bounded(listener, code, message_type, delivery.capacity as u32, delivery.policy.into())
+}
+
+/// `listener_create_queue` choosing how many messages the queue keeps until
+/// they are polled, `capacity`, and what happens when it is full,
+/// `overflow_policy` works the same as in `listener_add_callback_bounded`.
+/// With 0 (wait for room) the listener stops until the queue is polled.
+#[unsafe(no_mangle)]
+pub extern "C" fn listener_create_queue_bounded(
+    listener: u32,
+    code: u8,
+    message_type: u8,
+    capacity: u32,
+    overflow_policy: u8,
+) -> i32 {
+    let result = SubscribeCode::from_u8(code).and_then(|code| {
+        let (message_type, envelope) = SubscriptionType::from_flags(message_type)?;
+        let policy = OverflowPolicy::try_from(overflow_policy)
+            .map_err(|_| GlrError::InvalidOverflowPolicy(overflow_policy))?;
+        let delivery = Delivery { capacity: capacity as usize, policy };
+        glr_lib::dll_exports::functions::create_queue(listener, code, message_type, envelope, delivery)
+    });
+
+    to_status(result, |queue| queue as i32)
 }
 
+/// writes how many messages the overflow policy of the queue dropped to `dropped`
+///
+/// # Safety
 ///
+/// `dropped` must be null or point to a `uint64_t` that can be written.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn queue_dropped_events(queue: u32, dropped: *mut u64) -> i32 {
+    if dropped.is_null() {
+        return GlrError::NullPointer("dropped").into_status();
+    }
+
+    let result = glr_lib::dll_exports::functions::queue_dropped_events(queue).map(|count| unsafe {
+        *dropped = count;
+    });
+
+    to_status(result, |_| GLR_OK)
+}
+
+/// moves the oldest message of the queue into `buf`. Returns its length in
+/// bytes, 0 if there is no message or a negative status. If it doesn't fit
+/// in `buf_len` bytes it is left in the queue and `BufferTooSmall` (-8) is
+/// returned, `peek_event_len` gives the size needed.
+#[unsafe(no_mangle)]
+pub extern "C" fn poll_event(queue: u32, buf: *mut u8, buf_len: u32) -> i32 {
+    let buf: &mut [u8] = if buf.is_null() {
+        &mut []
+    } else {
+        unsafe { std::slice::from_raw_parts_mut(buf, buf_len as usize) }
+    };
+
+    to_status(glr_lib::dll_exports::functions::poll_event(queue, buf), |len| len as i32)
+}
+
+/// the length in bytes of the message `poll_event` would return, 0 if there
+/// is none, or a negative status.
+#[unsafe(no_mangle)]
+pub extern "C" fn peek_event_len(queue: u32) -> i32 {
+    to_status(glr_lib::dll_exports::functions::peek_event_len(queue), |len| len as i32)
+}
+
+/// blocks until the queue has a message or `timeout_ms` passed. Returns 1 if
+/// there is a message to poll, 0 if not or a negative status.
 #[unsafe(no_mangle)]
-pub extern "C" fn remove_callback(code: u8, channel_id: u32) {
-    let code = code.into();
+pub extern "C" fn wait_event(queue: u32, timeout_ms: u32) -> i32 {
+    let result = glr_lib::dll_exports::functions::wait_event(queue, Duration::from_millis(timeout_ms as u64));
+
+    to_status(result, |ready| ready as i32)
+}
 
-    glr_lib::dll_exports::functions::remove_callback(code, channel_id);
+/// unsubscribes the queue and drops the messages left in it. A `wait_event`
+/// on another thread returns right away.
+#[unsafe(no_mangle)]
+pub extern "C" fn destroy_queue(queue: u32) -> i32 {
+    to_status(glr_lib::dll_exports::functions::destroy_queue(queue), |_| GLR_OK)
 }
 
-/*
+/// runs the seed indexer on `level_str` (e.g. "R4E1") with `seed` and
+/// outputs the result to the callback as if the level was dropped into.
+/// Returns `NoDescriptor` (-15) without calling it if the level has no descriptor.
 #[unsafe(no_mangle)]
 pub extern "C" fn process_seed(
+    level_str: *const c_char,
     seed: i32,
     message_type: u8,
     context: *const c_void,
     event_callback_ptr: *const c_void,
-) {
-    let code = SubscribeCode::SeedIndexer;
-    let message_type = message_type.into();
-    let event_callback = if event_callback_ptr.is_null() {
-        None
-    } else {
-        Some(unsafe {
-            // Cast the void pointer into a function pointer
-            std::mem::transmute::<*const c_void, EventCallback>(event_callback_ptr)
-        })
-    };
-    
-    let callback_info = CallbackInfo::new(code, message_type, 0, context.into(), event_callback);
-    // glr_lib::dll_exports::functions::process_seed(seed, callback_info);
+) -> i32 {
+    let result = (|| {
+        if level_str.is_null() {
+            return Err(GlrError::NullPointer("level_str"));
+        }
+
+        let level = unsafe { CStr::from_ptr(level_str) }.to_string_lossy();
+        let level = level
+            .parse::<LevelDescriptor>()
+            .map_err(|_| GlrError::InvalidLevel(level.into_owned()))?;
+
+        let code = SubscribeCode::SeedIndexer.into();
+        let callback_info = callback_info(code, message_type, 0, context, event_callback_ptr)?;
+        glr_lib::dll_exports::functions::process_seed(level, seed, callback_info)
+    })();
+
+    to_status(result, |_| GLR_OK)
 }
-*/
 
+/// runs the parser of `code` over every file in `paths`, returning
+/// `ReadFailed` (-5) if some of them couldn't be read (the others are still
+/// processed).
 #[unsafe(no_mangle)]
 pub extern "C" fn process_paths(
     paths: *const *const c_char,
@@ -98,42 +368,158 @@ pub extern "C" fn process_paths(
     message_type: u8,
     context: *const c_void,
     event_callback_ptr: *const c_void,
-) {
-    if paths.is_null() {
-        return;
+) -> i32 {
+    let result = paths_from_ptr(paths, len).and_then(|pathbufs| {
+        let callback_info = callback_info(code, message_type, 0, context, event_callback_ptr)?;
+        glr_lib::dll_exports::functions::process_paths(pathbufs, callback_info)
+    });
+
+    to_status(result, |_| GLR_OK)
+}
+
+/// the same as `process_paths` but the files are processed on a new thread and
+/// it returns right away with the id of the job (always positive) or a negative
+/// status. The callback is called from that thread.
+///
+/// `progress_callback_ptr` (can be null) is a `ProgressCallback` called with the
+/// same `context` after every file and once more when the job stops, when its
+/// `state` is not running anymore. Call `destroy_job` once you are done with it.
+#[unsafe(no_mangle)]
+pub extern "C" fn process_paths_async(
+    paths: *const *const c_char,
+    len: u32,
+    code: u8,
+    message_type: u8,
+    context: *const c_void,
+    event_callback_ptr: *const c_void,
+    progress_callback_ptr: *const c_void,
+) -> i32 {
+    let result = paths_from_ptr(paths, len).and_then(|pathbufs| {
+        let callback_info = callback_info(code, message_type, 0, context, event_callback_ptr)?;
+        let progress_callback = (!progress_callback_ptr.is_null()).then(|| {
+            let callback = unsafe { std::mem::transmute::<*const c_void, ProgressCallback>(progress_callback_ptr) };
+            (callback, context.into())
+        });
+
+        Ok(glr_lib::dll_exports::functions::process_paths_async(pathbufs, callback_info, progress_callback))
+    });
+
+    to_status(result, |job| job as i32)
+}
+
+/// writes how far the job is to `progress`
+///
+/// # Safety
+///
+/// `progress` must be null or point to a `JobProgress` that can be written.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn job_progress(job: u32, progress: *mut JobProgress) -> i32 {
+    if progress.is_null() {
+        return GlrError::NullPointer("progress").into_status();
     }
 
-    let slice = unsafe { std::slice::from_raw_parts(paths, len as usize) };
+    let result = glr_lib::dll_exports::functions::job_progress(job).map(|job_progress| unsafe {
+        *progress = job_progress;
+    });
 
-    let pathbufs: Vec<PathBuf> = slice
-        .iter()
-        .filter_map(|&ptr| {
-            if ptr.is_null() {
-                None
-            } else {
-                let c_str = unsafe { CStr::from_ptr(ptr) };
-                Some(PathBuf::from(c_str.to_string_lossy().into_owned()))
-            }
-        })
-        .collect();
+    to_status(result, |_| GLR_OK)
+}
 
-    let code = code.into();
-    let message_type = message_type.into();
-    let event_callback = if event_callback_ptr.is_null() {
-        None
-    } else {
-        Some(unsafe {
-            // Cast the void pointer into a function pointer
-            std::mem::transmute::<*const c_void, EventCallback>(event_callback_ptr)
-        })
+/// blocks until the job stops or `timeout_ms` passed. Returns 1 if it
+/// stopped, 0 if not or a negative status.
+#[unsafe(no_mangle)]
+pub extern "C" fn wait_job(job: u32, timeout_ms: u32) -> i32 {
+    let result = glr_lib::dll_exports::functions::wait_job(job, Duration::from_millis(timeout_ms as u64));
+
+    to_status(result, |stopped| stopped as i32)
+}
+
+/// stops the job before the next line it would read. No more messages are
+/// sent after its progress callback reports it as cancelled. A job that
+/// already read all its files reports done instead.
+#[unsafe(no_mangle)]
+pub extern "C" fn cancel_job(job: u32) -> i32 {
+    to_status(glr_lib::dll_exports::functions::cancel_job(job), |_| GLR_OK)
+}
+
+/// cancels the job if it is still running, waits for its thread to stop and
+/// forgets it.
+#[unsafe(no_mangle)]
+pub extern "C" fn destroy_job(job: u32) -> i32 {
+    to_status(glr_lib::dll_exports::functions::destroy_job(job), |_| GLR_OK)
+}
+
+/// loads the level descriptors json at `file_path` on top of the ones
+/// included in the DLL, replacing levels with the same name. The file is
+/// read again whenever it changes and a new level is selected.
+///
+/// Returns the number of levels loaded or a negative status on failure, the
+/// error message is available from `glr_last_error`.
+#[unsafe(no_mangle)]
+pub extern "C" fn load_level_overrides(file_path: *const c_char) -> i32 {
+    let result = path_from_ptr(file_path, "file_path")
+        .and_then(glr_lib::dll_exports::functions::load_level_overrides);
+
+    to_status(result, |count| count as i32)
+}
+
+/// removes all level overrides, going back to the levels included in the DLL
+#[unsafe(no_mangle)]
+pub extern "C" fn clear_level_overrides() -> i32 {
+    glr_lib::dll_exports::functions::clear_level_overrides();
+    GLR_OK
+}
+
+/// `-9` if the override files changed and could not be loaded again (the
+/// previous levels are still used), `GLR_OK` otherwise
+#[unsafe(no_mangle)]
+pub extern "C" fn level_overrides_status() -> i32 {
+    to_status(glr_lib::dll_exports::functions::level_overrides_status(), |_| GLR_OK)
+}
+
+/// writes `message` to `buf` as a nul terminated string cut to `len` bytes
+fn copy_message(message: &str, buf: *mut c_char, len: u32) {
+    if buf.is_null() || len == 0 {
+        return;
+    }
+
+    let copied = message.len().min(len as usize - 1);
+    unsafe {
+        std::ptr::copy_nonoverlapping(message.as_ptr(), buf as *mut u8, copied);
+        *buf.add(copied) = 0;
+    }
+}
+
+/// writes the message of the last error returned on this thread to `buf` as a
+/// nul terminated string cut to `len` bytes. Returns the full length of the
+/// message (without the nul) so a bigger buffer can be used, or 0 if there
+/// was no error.
+#[unsafe(no_mangle)]
+pub extern "C" fn glr_last_error(buf: *mut c_char, len: u32) -> i32 {
+    let Some(message) = last_error() else {
+        return 0;
     };
 
-    let callback_info = CallbackInfo::new(code, message_type, 0, context.into(), event_callback);
+    copy_message(&message, buf, len);
+    message.len() as i32
+}
+
+/// the version of the BITDATA (`message_type` 2) layout. Compare it with the
+/// version your decoder was written for before reading any binary messages.
+#[unsafe(no_mangle)]
+pub extern "C" fn bitdata_schema_version() -> u32 {
+    BITDATA_SCHEMA_VERSION
+}
 
-    glr_lib::dll_exports::functions::process_paths(pathbufs, callback_info);
+/// the version of the envelope fields, the same as the `schema_version` of
+/// every envelope (`message_type` with the `0x80` flag).
+#[unsafe(no_mangle)]
+pub extern "C" fn envelope_schema_version() -> u32 {
+    ENVELOPE_SCHEMA_VERSION
 }
 
 #[unsafe(no_mangle)]
-pub extern "C" fn shutdown_all() {
+pub extern "C" fn shutdown_all() -> i32 {
     glr_lib::dll_exports::functions::shutdown_all();
+    GLR_OK
 }
@@ -4,6 +4,10 @@ use std::collections::HashMap;
 use std::env;
 use std::fs;
 
+#[path = "src/seed_gen/descriptor_validator.rs"]
+#[allow(dead_code)]
+mod descriptor_validator;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LevelData {
     pub build_seed: i32,
@@ -17,6 +21,19 @@ pub struct LevelData {
     #[serde(default)]
     pub bulk_keys_ovrl: Vec<Vec<ZoneLocationSpawn>>,
     pub staged_objectives: Vec<StagedObjective>,
+    #[serde(default)]
+    pub generation_order: Vec<GenerationStep>,
+    #[serde(default)]
+    pub inferred: bool,
+    #[serde(default)]
+    pub unmodeled_zones: Vec<ZoneIdentifier>,
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerationStep {
+    pub layer: u8,
+    pub dimension: u8,
+    #[serde(default)]
+    pub objectives: Vec<usize>,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ZoneLocationSpawn {
@@ -54,6 +71,8 @@ pub struct SpawnObject {
 
     pub alloc_type: AllocType,
     #[serde(default)] pub skip_before_alloc: usize,
+    #[serde(default)] pub rule: String,
+    #[serde(default)] pub is_bugged: bool,
 }
 fn generate_1_usize() -> usize {
     1
@@ -149,6 +168,18 @@ pub struct StagedObjective {
 pub struct CollectableMapper {
     map: HashMap<String, HashMap<u64, HashMap<u64, u64>>>,
 }
+#[derive(Serialize, Deserialize)]
+pub struct LayoutVariant {
+    pub name: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub verified: bool,
+}
+#[derive(Serialize, Deserialize)]
+pub struct LayoutVariants {
+    map: BTreeMap<String, BTreeMap<String, LayoutVariant>>,
+}
 
 fn main() {
     
@@ -157,16 +188,43 @@ fn main() {
         .parent()
         .unwrap()
         .join("resources");
+
+    // the resources live outside of this package so cargo doesn't watch them by default
+    for file in ["level_descriptors.json", "collectable_maps.ron", "layout_variants.ron"] {
+        println!("cargo:rerun-if-changed={}", resources_path.join(file).display());
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/seed_gen/descriptor_validator.rs");
+
     let descriptors_json = fs::read_to_string(resources_path.join("level_descriptors.json")).unwrap();
     let collectables_json = fs::read_to_string(resources_path.join("collectable_maps.ron")).unwrap();
+    let variants_ron = fs::read_to_string(resources_path.join("layout_variants.ron")).unwrap();
 
     // 2️⃣ Deserialize them
     let descriptors: BTreeMap<String, LevelData> = serde_json::from_str(&descriptors_json).unwrap();
     let collectables: CollectableMapper = ron::from_str(&collectables_json).unwrap();
+    let variants: LayoutVariants = ron::from_str(&variants_ron).unwrap();
+
+    for (level, hashes) in &variants.map {
+        for hash in hashes.keys() {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                panic!("layout_variants.ron: {level} has an invalid hash '{hash}', expected 64 hex chars");
+            }
+        }
+    }
+
+    let issues = descriptor_validator::validate_descriptors(&descriptors_json).unwrap();
+    for issue in &issues {
+        println!("cargo:warning={issue}");
+    }
+    if issues.iter().any(|v| v.severity == descriptor_validator::Severity::Error) {
+        panic!("level_descriptors.json has errors, see the warnings above");
+    }
 
     // 3️⃣ Serialize to binary
     let collectables_bin = bincode::serialize(&collectables).unwrap();
     let descriptors_bin = bincode::serialize(&descriptors).unwrap();
+    let variants_bin = bincode::serialize(&variants).unwrap();
 
     let interop_dir = resources_path.parent()
         .unwrap()
@@ -178,9 +236,12 @@ fn main() {
         .expect("Failed to write descriptors binary");
     fs::write(interop_dir.join("collectable_maps.bin"), &collectables_bin)
         .expect("Failed to write collectables binary");
+    fs::write(interop_dir.join("layout_variants.bin"), &variants_bin)
+        .expect("Failed to write layout variants binary");
 
     // checks:
     let _: BTreeMap<String, LevelData> = bincode::deserialize(&descriptors_bin).unwrap();
     let _: CollectableMapper = bincode::deserialize(&collectables_bin).unwrap();
+    let _: LayoutVariants = bincode::deserialize(&variants_bin).unwrap();
     
 }
@@ -267,6 +267,8 @@ impl Tokenizer for AllTokenizer {
 mod tests {
     use std::{env, fs::File, io::Read};
 
+    use glr_core::data::ObjectiveFunction;
+
     use super::*;
 
     #[allow(dead_code)]
@@ -314,4 +316,31 @@ mod tests {
             })
             .collect()
     }
+
+    /// only the position of the match and the words the parser reads (13 and 18) follow the game's line
+    fn objective_line(function: &str, area: &str) -> String {
+        format!("00:01:02.345 - <color=#C84800>WO : LG_Distribute_WardenObjective, placing warden objective item with function for objective {function} in zone 3 at {area}")
+    }
+
+    #[test]
+    fn test_objective_functions() {
+        let lines = [
+            objective_line("TerminalUplink", "12_area"),
+            objective_line("Reactor_Startup", "4_area"),
+            objective_line("PowerCellDistribution", "7_area"),
+            objective_line("CentralGeneratorCluster", "2_area"),
+        ]
+        .join("\n");
+
+        let tokens: Vec<Token> = GenerationTokenizer.tokenize(&lines).into_iter().map(|(_, token)| token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::ObjectiveSpawnedOverride(12, ObjectiveFunction::TerminalUplink),
+                Token::ObjectiveSpawnedOverride(4, ObjectiveFunction::Reactor_Startup),
+                Token::ObjectiveSpawnedOverride(7, ObjectiveFunction::PowerCellDistribution),
+                Token::ObjectiveSpawnedOverride(2, ObjectiveFunction::CentralGeneratorCluster),
+            ]
+        );
+    }
 }
@@ -0,0 +1,121 @@
+//! the BITDATA (`SubscriptionType::BITDATA`) message layout.
+//!
+//! Every message is one value serialized with bincode 1.3 and its default
+//! options: little endian, fixed size integers, `usize` as u64, strings and
+//! vecs prefixed by their length as u64, `Option` as a u8 tag (0 None, 1 Some)
+//! followed by the value, and enum variants as a u32 index in declaration
+//! order (not the `repr` value) followed by their fields. Messages are given
+//! to a `BinaryEventCallback` with their length, there is no terminator.
+//!
+//! Which type a message is depends on the subscribe code:
+//! Tokenizer `Token`, RunInfo `RunGeneratorResult`, Mapper `Location`,
+//! SeedIndexer `OutputSeedIndexer` and SeedTrace `OutputSeedTrace`.
+//! With `ENVELOPE_FLAG` it is an `Envelope` of that type instead.
+
+/// bumped whenever a variant or field of the output types is added, removed
+/// or reordered, since that changes how every following byte is read.
+pub const BITDATA_SCHEMA_VERSION: u32 = 1;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use glr_core::{
+        location::{ItemIdentifier, Location},
+        run::TimedRun,
+        run_gen_result::RunGeneratorResult,
+        seed_indexer_result::{Confidence, ItemKind, OutputSeedIndexer, Provenance},
+        split::NamedSplit,
+        time::Time,
+        token::Token,
+    };
+    use serde::{Serialize, de::DeserializeOwned};
+
+    use crate::{
+        dll_exports::{csv_record::CsvRecord, enums::SubscriptionType},
+        seed_gen::{consumers::base_consumer::Consumer, test_levels, unity_random::UnityRandom},
+    };
+
+    /// encodes like the callbacks do and checks the decoded value is the same
+    fn round_trip<T: Serialize + DeserializeOwned + CsvRecord>(value: &T) -> Vec<u8> {
+        let bytes = SubscriptionType::BITDATA.convert(value).unwrap();
+        let decoded: T = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(value).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn test_bitdata_layout() {
+        // variant 9, seed as u64
+        assert_eq!(round_trip(&Token::SessionSeed(5)), [9, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut expected = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend(b"KeyZ");
+        expected.extend(51u64.to_le_bytes());
+        expected.extend(3u64.to_le_bytes());
+        assert_eq!(round_trip(&Location::ColoredKey("KeyZ".to_owned(), 51, 3)), expected);
+
+        // ItemKind is written as its name, Confidence as its variant index
+        let key = OutputSeedIndexer::Key(
+            ItemKind::Cell,
+            1,
+            51,
+            -1,
+            Provenance {
+                confidence: Confidence::Overflow,
+                rule: "r".to_owned(),
+            },
+        );
+        let mut expected = vec![1, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend(b"Cell");
+        expected.push(1);
+        expected.extend(51i32.to_le_bytes());
+        expected.extend((-1i32).to_le_bytes());
+        expected.extend([3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend(b"r");
+        assert_eq!(round_trip(&key), expected);
+    }
+
+    #[test]
+    fn test_bitdata_decodes_outputs() {
+        let level = "R2C1".parse().unwrap();
+        let time = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+
+        for token in [
+            Token::PlayerJoinedLobby("\0name".to_owned()),
+            Token::TimeSessionStart(time),
+            Token::SelectExpedition(level, -12),
+            Token::ItemAllocated("KEY_PURPLE_123".try_into().unwrap()),
+            Token::Invalid,
+        ] {
+            round_trip(&token);
+        }
+
+        let mut run = TimedRun::new("R1A1".parse().unwrap(), vec!["a".to_owned(), "b".to_owned()], time);
+        run.add_split(NamedSplit::new(Time::from_stamp(1234), "ZONE_50".to_owned()));
+        for result in [
+            RunGeneratorResult::GameStarted("R1A1".parse().unwrap(), 4, time),
+            RunGeneratorResult::PlayerDeath("a".to_owned()),
+            RunGeneratorResult::LevelRun(run),
+        ] {
+            round_trip(&result);
+        }
+
+        for location in [
+            Location::Gatherable(ItemIdentifier::Unknown(7), 50, 2),
+            Location::BigCollectable(ItemIdentifier::Cell, 51),
+            Location::GenerationStarted("R1A1".to_owned()),
+        ] {
+            round_trip(&location);
+        }
+
+        let levels = test_levels();
+        let mut layout = Vec::new();
+        levels["R1A1"].take(&mut UnityRandom::from(1), &mut layout);
+
+        assert!(!layout.is_empty());
+        for item in &layout {
+            round_trip(item);
+        }
+    }
+}
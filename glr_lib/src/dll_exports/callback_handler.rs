@@ -1,16 +1,32 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-use glr_core::{time::Time, token::Token};
+use chrono::Utc;
+use glr_core::{envelope::{ENVELOPE_SCHEMA_VERSION, Envelope}, time::Time, token::Token};
 use serde::Serialize;
 
-use crate::{core::token_parser::TokenParser, dll_exports::{structs::CallbackInfo, token_parsers::TokenParserInner}, output_trait::OutputTrait};
+use crate::{core::token_parser::TokenParser, dll_exports::{csv_record::CsvRecord, filter::variant_name, structs::CallbackInfo, token_parsers::TokenParserInner}, output_trait::OutputTrait};
+
+/// shared by every subscription so enveloped messages can be ordered across channels
+static GLOBAL_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// the callbacks of a subscribe code and where the token being parsed came from
+#[derive(Default)]
+pub struct Callbacks {
+    callbacks: HashMap<u32, CallbackInfo>,
+
+    time: Time,
+    source: Option<String>,
+}
 
 #[derive(Default)]
 pub struct CallbackWrapper<P>
-where 
+where
     P: TokenParserInner {
 
-    callbacks: HashMap<u32, CallbackInfo>,
+    callbacks: Callbacks,
     token_parser: P,
 
 }
@@ -18,19 +34,36 @@ where
 impl<P: TokenParserInner> CallbackWrapper<P> {
 
     pub fn add_callback(&mut self, callback: CallbackInfo) {
-        self.callbacks.insert(callback.get_id(), callback);
+        let message_type = callback.get_message_type();
+        let header = match callback.has_envelope() {
+            true => message_type.header::<Envelope<P::Output>>(),
+            false => message_type.header::<P::Output>(),
+        };
+        if let Some(header) = header {
+            callback.send(&header);
+        }
+        self.callbacks.callbacks.insert(callback.get_id(), callback);
     }
 
     pub fn remove_callback(&mut self, callback_id: u32) {
-        self.callbacks.remove(&callback_id);
+        self.callbacks.callbacks.remove(&callback_id);
+    }
+
+    pub fn has_callbacks(&self) -> bool {
+        !self.callbacks.callbacks.is_empty()
+    }
+
+    /// the log file the next tokens come from
+    pub fn set_source(&mut self, source: Option<String>) {
+        self.callbacks.source = source;
     }
 
 }
 
 impl<P> CallbackWrapper<P>
-where 
+where
     P: TokenParserInner + Default {
-    
+
     pub fn reset_token_parser(&mut self) {
         self.token_parser = P::default();
     }
@@ -38,20 +71,116 @@ where
 
 impl<P: TokenParserInner> TokenParser for CallbackWrapper<P> {
     fn parse_token(&mut self, time: Time, token: &Token) {
+        self.callbacks.time = time;
         self.token_parser.parse(time, token, &mut self.callbacks);
     }
 }
 
-impl<O: Serialize> OutputTrait<O> for HashMap<u32, CallbackInfo> {
+impl<O: Serialize + CsvRecord> OutputTrait<O> for Callbacks {
     fn output(&mut self, data: O) {
-        for callback in self.values() {
-            if let Some(event) = callback.get_event_callback() {
-                let converter = callback.get_message_type();
-                let context = callback.get_context();
-                if let Some(result_string) = converter.convert(&data) {
-                    event(context.get_ptr(), result_string.as_ptr())
+        // only taken if one of the callbacks wants an envelope
+        let mut global = None;
+        let name = variant_name(&data);
+
+        for callback in self.callbacks.values_mut() {
+            if !callback.accepts(name) {
+                continue;
+            }
+
+            let message_type = callback.get_message_type();
+
+            let message = match callback.has_envelope() {
+                true => {
+                    let (global_sequence, emitted_at) = *global
+                        .get_or_insert_with(|| (GLOBAL_SEQUENCE.fetch_add(1, Ordering::Relaxed), Utc::now()));
+
+                    message_type.convert(&Envelope {
+                        schema_version: ENVELOPE_SCHEMA_VERSION,
+                        code: callback.get_code().into(),
+                        sequence: callback.next_sequence(),
+                        global_sequence,
+                        log_time: self.time,
+                        emitted_at,
+                        source: self.source.clone(),
+                        payload: &data,
+                    })
                 }
+                false => message_type.convert(&data),
+            };
+
+            if let Some(message) = message {
+                callback.send(&message);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::c_void, sync::Mutex};
+
+    use glr_core::data::LevelDescriptor;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::dll_exports::{
+        enums::{SubscribeCode, SubscriptionType},
+        structs::CallbackFn,
+        token_parsers::token_parser_base::TokenParserBase,
+    };
+
+    extern "C" fn collect(context: *const c_void, message: *const std::ffi::c_char) {
+        let messages = unsafe { &*(context as *const Mutex<Vec<String>>) };
+        let message = unsafe { std::ffi::CStr::from_ptr(message) };
+        messages.lock().unwrap().push(message.to_string_lossy().into_owned());
+    }
+
+    #[test]
+    fn test_envelope() {
+        let messages = Mutex::new(Vec::<String>::new());
+        let callback = |id: u32, message_type: u8| {
+            let (message_type, envelope) = SubscriptionType::from_flags(message_type).unwrap();
+            CallbackInfo::new(
+                SubscribeCode::Tokenizer,
+                message_type,
+                id,
+                (&messages as *const Mutex<Vec<String>>).into(),
+                Some(CallbackFn::Text(collect)),
+            )
+            .with_envelope(envelope)
+        };
+
+        let mut wrapper = CallbackWrapper::<TokenParserBase>::default();
+        wrapper.add_callback(callback(0, 0x81));
+        wrapper.set_source(Some("log.txt".to_owned()));
+        wrapper.parse_token(Time::from_stamp(1000), &Token::SessionSeed(5));
+        wrapper.parse_token(Time::from_stamp(2000), &Token::SelectExpedition(LevelDescriptor::default(), 1));
+        wrapper.add_callback(callback(1, 0x83));
+        wrapper.add_callback(callback(2, 0x01));
+        wrapper.parse_token(Time::from_stamp(3000), &Token::DoorOpen);
+
+        let messages = messages.into_inner().unwrap();
+        let json: Vec<Value> = messages
+            .iter()
+            .filter_map(|m| serde_json::from_str::<Value>(m).ok())
+            .filter(|m| m.get("sequence").is_some())
+            .collect();
+
+        assert_eq!(json.len(), 3);
+        assert_eq!(json[0]["payload"], serde_json::json!({"SessionSeed": 5}));
+        assert_eq!(json[0]["log_time"], 1000);
+        assert_eq!(json[0]["source"], "log.txt");
+        assert_eq!(json[0]["code"], 1);
+        assert_eq!(json[0]["schema_version"], ENVELOPE_SCHEMA_VERSION);
+        assert_eq!(json.iter().map(|m| m["sequence"].as_u64().unwrap()).collect::<Vec<_>>(), [0, 1, 2]);
+
+        let global = |m: &Value| m["global_sequence"].as_u64().unwrap();
+        assert!(global(&json[0]) < global(&json[1]) && global(&json[1]) < global(&json[2]));
+
+        // the csv subscription gets its header and then a row with the same global sequence
+        assert!(messages.contains(&"schema_version,code,sequence,global_sequence,log_time,emitted_at,source,event,value_1,value_2".to_owned()));
+        let row = messages.iter().find(|m| m.ends_with(",log.txt,DoorOpen,,")).unwrap();
+        assert!(row.starts_with(&format!("1,1,0,{},00:00:03.000,", global(&json[2]))));
+        assert!(messages.contains(&"\"DoorOpen\"".to_owned()));
+    }
+}
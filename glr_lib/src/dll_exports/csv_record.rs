@@ -0,0 +1,437 @@
+//! the CSV (`SubscriptionType::CSV`) message layout.
+//!
+//! Every output type has one flat list of columns, the first one always
+//! being `event` (the variant name). Columns a variant doesn't use are left
+//! empty. A CSV subscriber first gets the header row as its own message and
+//! then one message per output, which can be more than one row when the
+//! output has nested data (e.g. the splits of a `LevelRun`). Rows inside a
+//! message are separated by `\n` and the message doesn't end with one.
+
+use std::fmt::Display;
+
+use glr_core::{
+    envelope::Envelope,
+    location::Location,
+    run_gen_result::RunGeneratorResult,
+    seed_indexer_result::{OutputSeedIndexer, OutputSeedTrace, Provenance},
+    split::Split,
+    token::Token,
+};
+
+use crate::seed_gen::layout_variants::hash_to_hex;
+
+pub trait CsvRecord {
+    /// the column names, the first one is always `event` unless it's in an envelope
+    fn header() -> Vec<&'static str>;
+
+    /// the rows this value is flattened to, each with one field per column of `header()`
+    fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// quotes the field if it contains a separator, quote or line break.
+/// nul characters are dropped since the message is given as a C string.
+pub(crate) fn escape(field: &str) -> String {
+    let field = field.replace('\0', "");
+
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field,
+    }
+}
+
+fn join_row<S: AsRef<str>>(fields: &[S]) -> String {
+    fields.iter().map(|f| escape(f.as_ref())).collect::<Vec<_>>().join(",")
+}
+
+pub fn header_row<T: CsvRecord>() -> String {
+    join_row(&T::header())
+}
+
+pub fn to_csv<T: CsvRecord>(value: &T) -> String {
+    value.rows().iter().map(|row| join_row(row)).collect::<Vec<_>>().join("\n")
+}
+
+/// a row of `header` that is filled by column name
+struct CsvRow {
+    header: Vec<&'static str>,
+    fields: Vec<String>,
+}
+
+impl CsvRow {
+    fn new(header: Vec<&'static str>, event: &str) -> Self {
+        let mut fields = vec![String::new(); header.len()];
+        fields[0] = event.to_owned();
+
+        Self { header, fields }
+    }
+
+    fn set(mut self, column: &str, value: impl Display) -> Self {
+        let id = self.header.iter().position(|c| *c == column);
+        debug_assert!(id.is_some(), "{column} is not a column");
+
+        if let Some(id) = id {
+            self.fields[id] = value.to_string();
+        }
+        self
+    }
+
+    fn provenance(self, provenance: &Provenance) -> Self {
+        self.set("confidence", format!("{:?}", provenance.confidence))
+            .set("rule", &provenance.rule)
+    }
+}
+
+impl From<CsvRow> for Vec<String> {
+    fn from(value: CsvRow) -> Self {
+        value.fields
+    }
+}
+
+/// the fields of the token in the order they are declared
+impl CsvRecord for Token {
+    fn header() -> Vec<&'static str> {
+        vec!["event", "value_1", "value_2"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let row = |event: &str| CsvRow::new(Self::header(), event);
+
+        let row = match self {
+            Token::GeneratingLevel => row("GeneratingLevel"),
+            Token::PlayerJoinedLobby(name) => row("PlayerJoinedLobby").set("value_1", name),
+            Token::PlayerLeftLobby(name) => row("PlayerLeftLobby").set("value_1", name),
+            Token::PlayerDown(name) => row("PlayerDown").set("value_1", name),
+            Token::PlayerExitElevator(name) => row("PlayerExitElevator").set("value_1", name),
+            Token::UserExitLobby => row("UserExitLobby"),
+            Token::GameStateManagerChange(from, to) => row("GameStateManagerChange")
+                .set("value_1", format!("{from:?}"))
+                .set("value_2", format!("{to:?}")),
+            Token::PlayerStateChange(name, state) => row("PlayerStateChange")
+                .set("value_1", name)
+                .set("value_2", format!("{state:?}")),
+            Token::TimeSessionStart(date) => row("TimeSessionStart").set("value_1", date.to_rfc3339()),
+            Token::SessionSeed(seed) => row("SessionSeed").set("value_1", seed),
+            Token::ItemAllocated(key) => row("ItemAllocated").set("value_1", key),
+            Token::ItemSpawn(zone, id) => row("ItemSpawn").set("value_1", zone).set("value_2", id),
+            Token::CollectableAllocated(zone) => row("CollectableAllocated").set("value_1", zone),
+            Token::ObjectiveSpawnedOverride(id, function) => row("ObjectiveSpawnedOverride")
+                .set("value_1", id)
+                .set("value_2", format!("{function:?}")),
+            Token::CollectableItemID(id) => row("CollectableItemID").set("value_1", id),
+            Token::CollectableItemSeed(seed) => row("CollectableItemSeed").set("value_1", seed),
+            Token::DimensionIncrease => row("DimensionIncrease"),
+            Token::DimensionReset => row("DimensionReset"),
+            Token::SelectExpedition(level, seed) => row("SelectExpedition").set("value_1", level).set("value_2", seed),
+            Token::PlayerDroppedInLevel(id) => row("PlayerDroppedInLevel").set("value_1", id),
+            Token::DoorOpen => row("DoorOpen"),
+            Token::CheckpointReset => row("CheckpointReset"),
+            Token::BulkheadScanDone => row("BulkheadScanDone"),
+            Token::SecondaryDone => row("SecondaryDone"),
+            Token::OverloadDone => row("OverloadDone"),
+            Token::GameEndAbort => row("GameEndAbort"),
+            Token::LogFileEnd => row("LogFileEnd"),
+            Token::BadPacketSentByPlayer(name) => row("BadPacketSentByPlayer").set("value_1", name),
+            Token::Invalid => row("Invalid"),
+        };
+
+        vec![row.into()]
+    }
+}
+
+/// a `LevelRun` is a row with the totals followed by a `LevelRunSplit` row per split
+impl CsvRecord for RunGeneratorResult {
+    fn header() -> Vec<&'static str> {
+        vec![
+            "event", "level", "name", "time", "players", "date", "secondary", "overload", "checkpoints", "win",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let row = |event: &str| CsvRow::new(Self::header(), event);
+
+        let row = match self {
+            RunGeneratorResult::GameStarted(level, players, date) => row("GameStarted")
+                .set("level", level)
+                .set("players", players)
+                .set("date", date.to_rfc3339()),
+            RunGeneratorResult::SplitAdded(split) => row("SplitAdded")
+                .set("name", split.get_name())
+                .set("time", split.get_time().to_string()),
+            RunGeneratorResult::PlayerCountUpdate(players) => row("PlayerCountUpdate").set("players", players),
+            RunGeneratorResult::PlayerDeath(name) => row("PlayerDeath").set("name", name),
+            RunGeneratorResult::SecondaryDone => row("SecondaryDone"),
+            RunGeneratorResult::OverloadDone => row("OverloadDone"),
+            RunGeneratorResult::CheckpointUsed => row("CheckpointUsed"),
+            RunGeneratorResult::LevelRun(run) => {
+                let level = run.get_name();
+                let mut players: Vec<&str> = run.get_players_iter().map(String::as_str).collect();
+                players.sort();
+
+                let total = row("LevelRun")
+                    .set("level", level)
+                    .set("name", players.join(";"))
+                    .set("time", run.get_time().to_string())
+                    .set("players", run.get_player_count())
+                    .set("date", run.get_utc().to_rfc3339())
+                    .set("secondary", run.get_secondary())
+                    .set("overload", run.get_overload())
+                    .set("checkpoints", run.get_checkpoint_count())
+                    .set("win", run.get_is_win());
+
+                let splits = run.iter_splits().map(|split| {
+                    row("LevelRunSplit")
+                        .set("level", level)
+                        .set("name", split.get_name())
+                        .set("time", split.get_time().to_string())
+                        .into()
+                });
+
+                return std::iter::once(total.into()).chain(splits).collect();
+            }
+        };
+
+        vec![row.into()]
+    }
+}
+
+/// `id` is the area for terminals, reactors and generators
+impl CsvRecord for Location {
+    fn header() -> Vec<&'static str> {
+        vec!["event", "name", "zone", "id"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let row = |event: &str, zone: &u64| CsvRow::new(Self::header(), event).set("zone", zone);
+
+        let row = match self {
+            Location::ColoredKey(name, zone, id) => row("ColoredKey", zone).set("name", name).set("id", id),
+            Location::BulkheadKey(name, zone, id) => row("BulkheadKey", zone).set("name", name).set("id", id),
+            Location::Gatherable(item, zone, id) => {
+                row("Gatherable", zone).set("name", format!("{item:?}")).set("id", id)
+            }
+            Location::BigObjective(name, zone, id) => row("BigObjective", zone).set("name", name).set("id", id),
+            Location::Terminal(function, zone, id) => {
+                row("Terminal", zone).set("name", format!("{function:?}")).set("id", id)
+            }
+            Location::Reactor(function, zone, id) => {
+                row("Reactor", zone).set("name", format!("{function:?}")).set("id", id)
+            }
+            Location::Generator(zone, id) => row("Generator", zone).set("id", id),
+            Location::GeneratorCluster(zone, id) => row("GeneratorCluster", zone).set("id", id),
+            Location::BigCollectable(item, zone) => row("BigCollectable", zone).set("name", format!("{item:?}")),
+            Location::GenerationStarted(name) => CsvRow::new(Self::header(), "GenerationStarted").set("name", name),
+        };
+
+        vec![row.into()]
+    }
+}
+
+/// a `ZoneResourceSummary` is one row per resource total
+impl CsvRecord for OutputSeedIndexer {
+    fn header() -> Vec<&'static str> {
+        vec![
+            "event", "name", "dimension", "zone", "id", "count", "uses", "value", "remainder", "lock", "confidence",
+            "rule", "notes",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let row = |event: &str| CsvRow::new(Self::header(), event);
+
+        let row = match self {
+            OutputSeedIndexer::Seed(seed) => row("Seed").set("value", seed),
+            OutputSeedIndexer::Key(kind, dimension, zone, id, provenance) => row("Key")
+                .set("name", kind)
+                .set("dimension", dimension)
+                .set("zone", zone)
+                .set("id", id)
+                .provenance(provenance),
+            OutputSeedIndexer::ResourcePack(resource, dimension, zone, id, size, amount, remainder, provenance) => {
+                row("ResourcePack")
+                    .set("name", format!("{resource:?}"))
+                    .set("dimension", dimension)
+                    .set("zone", zone)
+                    .set("id", id)
+                    .set("count", size)
+                    .set("value", amount)
+                    .set("remainder", remainder)
+                    .provenance(provenance)
+            }
+            OutputSeedIndexer::GenerationOverflow(count) => row("GenerationOverflow").set("count", count),
+            OutputSeedIndexer::GenerationOverflowHash(hash) => {
+                row("GenerationOverflowHash").set("name", hash_to_hex(hash))
+            }
+            OutputSeedIndexer::KnownLayout(name, notes) => row("KnownLayout").set("name", name).set("notes", notes),
+            OutputSeedIndexer::LockStateChange(dimension, zone, id, lock) => row("LockStateChange")
+                .set("dimension", dimension)
+                .set("zone", zone)
+                .set("id", id)
+                .set("lock", format!("{lock:?}")),
+            OutputSeedIndexer::GenerationEnd => row("GenerationEnd"),
+            OutputSeedIndexer::GenerationStart(name) => row("GenerationStart").set("name", name),
+            OutputSeedIndexer::ZoneGenEnded(count) => row("ZoneGenEnded").set("count", count),
+            OutputSeedIndexer::ZoneResourceSummary(dimension, zone, totals) => {
+                let zone_row = || row("ZoneResourceSummary").set("dimension", dimension).set("zone", zone);
+                if totals.is_empty() {
+                    return vec![zone_row().into()];
+                }
+
+                return totals
+                    .iter()
+                    .map(|total| {
+                        zone_row()
+                            .set("name", format!("{:?}", total.resource))
+                            .set("lock", format!("{:?}", total.lock))
+                            .set("count", total.packs)
+                            .set("uses", total.uses)
+                            .set("value", total.amount)
+                            .into()
+                    })
+                    .collect();
+            }
+            OutputSeedIndexer::ProcessFailed => row("ProcessFailed"),
+        };
+
+        vec![row.into()]
+    }
+}
+
+impl CsvRecord for OutputSeedTrace {
+    fn header() -> Vec<&'static str> {
+        vec!["event", "stream", "index", "value", "label", "layer", "dimension", "zone", "id"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let row = |event: &str| CsvRow::new(Self::header(), event);
+        let option = |v: Option<String>| v.unwrap_or_default();
+
+        let row = match self {
+            OutputSeedTrace::GenerationStart(name) => row("GenerationStart").set("label", name),
+            OutputSeedTrace::Value(entry) => row("Value")
+                .set("stream", format!("{:?}", entry.stream))
+                .set("index", entry.index)
+                .set("value", entry.value)
+                .set("label", &entry.label)
+                .set("layer", option(entry.layer.map(|v| v.to_string())))
+                .set("dimension", option(entry.dimension.map(|v| v.to_string())))
+                .set("zone", option(entry.zone.map(|v| v.to_string())))
+                .set("id", option(entry.id.map(|v| v.to_string()))),
+            OutputSeedTrace::GenerationEnd => row("GenerationEnd"),
+        };
+
+        vec![row.into()]
+    }
+}
+
+impl<T: CsvRecord> CsvRecord for &T {
+    fn header() -> Vec<&'static str> {
+        T::header()
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        (*self).rows()
+    }
+}
+
+/// the envelope columns followed by the columns of the payload, every row
+/// of the payload gets the envelope columns
+impl<T: CsvRecord> CsvRecord for Envelope<T> {
+    fn header() -> Vec<&'static str> {
+        let mut header = vec![
+            "schema_version", "code", "sequence", "global_sequence", "log_time", "emitted_at", "source",
+        ];
+        header.extend(T::header());
+        header
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let envelope = [
+            self.schema_version.to_string(),
+            self.code.to_string(),
+            self.sequence.to_string(),
+            self.global_sequence.to_string(),
+            self.log_time.to_string(),
+            self.emitted_at.to_rfc3339(),
+            self.source.clone().unwrap_or_default(),
+        ];
+
+        self.payload
+            .rows()
+            .into_iter()
+            .map(|row| envelope.iter().cloned().chain(row).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use glr_core::{
+        run::TimedRun,
+        seed_indexer_result::{Confidence, ItemKind, LockState, ResourceTotal, ResourceType},
+        split::NamedSplit,
+        time::Time,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_csv_rows() {
+        assert_eq!(header_row::<Location>(), "event,name,zone,id");
+        assert_eq!(to_csv(&Location::Generator(50, 3)), "Generator,,50,3");
+        assert_eq!(
+            to_csv(&Token::PlayerJoinedLobby("a, \"b\"".to_owned())),
+            "PlayerJoinedLobby,\"a, \"\"b\"\"\","
+        );
+
+        let key = OutputSeedIndexer::Key(
+            ItemKind::ColoredKey { for_zone: 51 },
+            0,
+            50,
+            3,
+            Provenance {
+                confidence: Confidence::Verified,
+                rule: "zone L0 D0 Z51 unlocked_by".to_owned(),
+            },
+        );
+        assert_eq!(to_csv(&key), "Key,KeyZ51,0,50,3,,,,,,Verified,zone L0 D0 Z51 unlocked_by,");
+
+        let summary = OutputSeedIndexer::ZoneResourceSummary(
+            0,
+            50,
+            vec![
+                ResourceTotal {
+                    resource: ResourceType::Healthpack,
+                    lock: LockState::Unlocked,
+                    packs: 2,
+                    uses: 6,
+                    amount: 1.2,
+                },
+                ResourceTotal {
+                    resource: ResourceType::Ammopack,
+                    lock: LockState::HackLock,
+                    packs: 1,
+                    uses: 4,
+                    amount: 0.8,
+                },
+            ],
+        );
+        assert_eq!(
+            to_csv(&summary),
+            "ZoneResourceSummary,Healthpack,0,50,,2,6,1.2,,Unlocked,,,\n\
+             ZoneResourceSummary,Ammopack,0,50,,1,4,0.8,,HackLock,,,"
+        );
+
+        let time = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        let mut run = TimedRun::new("R1A1".parse().unwrap(), vec!["b".to_owned(), "a".to_owned()], time);
+        run.add_split(NamedSplit::new(Time::from_stamp(61_000), "ZONE_50".to_owned()));
+        run.add_split(NamedSplit::new(Time::from_stamp(2_500), "ZONE_51".to_owned()));
+        run.add_win();
+        assert_eq!(
+            to_csv(&RunGeneratorResult::LevelRun(run)),
+            "LevelRun,R1A1,a;b,00:01:03.500,2,2025-01-02T03:04:05+00:00,false,false,0,true\n\
+             LevelRunSplit,R1A1,ZONE_50,00:01:01.000,,,,,,\n\
+             LevelRunSplit,R1A1,ZONE_51,00:00:02.500,,,,,,"
+        );
+    }
+}
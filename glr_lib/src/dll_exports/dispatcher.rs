@@ -0,0 +1,155 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use crate::dll_exports::event_queue::{EventQueue, OverflowPolicy};
+
+/// the queue a live subscription is given and what to do when it's full.
+/// By default it drops the oldest messages so a slow callback never slows
+/// down the listener, `OverflowPolicy::Block` has to be asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for Delivery {
+    fn default() -> Self {
+        Delivery {
+            capacity: 1024,
+            policy: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+/// gives the messages of a subscription to its callback on a thread of its own
+/// so a slow callback doesn't stop the parsing for the others
+#[derive(Debug)]
+pub struct Dispatcher {
+    queue: Arc<EventQueue>,
+    join: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Dispatcher {
+    pub fn spawn(delivery: Delivery, deliver: impl Fn(&[u8]) + Send + 'static) -> Dispatcher {
+        let queue = Arc::new(EventQueue::bounded(delivery.capacity, delivery.policy));
+
+        let thread_queue = Arc::clone(&queue);
+        let join = thread::spawn(move || {
+            while let Some(message) = thread_queue.pop_wait() {
+                deliver(&message);
+            }
+        });
+
+        Dispatcher {
+            queue,
+            join: Mutex::new(Some(join)),
+        }
+    }
+
+    pub fn get_queue(&self) -> &Arc<EventQueue> {
+        &self.queue
+    }
+
+    /// drops the messages left (counting them as dropped) and waits for the
+    /// callback being called to return, so the callback is never called after
+    /// this returns. Called from the callback itself it can't wait for it.
+    pub fn stop(&self) {
+        self.queue.close();
+
+        let join = self.join.lock().ok().and_then(|mut join| join.take());
+        if let Some(jh) = join
+            && jh.thread().id() != thread::current().id()
+        {
+            let _ = jh.join();
+        }
+    }
+}
+
+impl Drop for Dispatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{c_char, c_void},
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        time::{Duration, Instant},
+    };
+
+    use glr_core::{time::Time, token::Token};
+
+    use super::*;
+    use crate::{
+        core::token_parser::TokenParser,
+        dll_exports::{
+            callback_handler::CallbackWrapper,
+            enums::{SubscribeCode, SubscriptionType},
+            structs::{CallbackFn, CallbackInfo},
+            token_parsers::token_parser_base::TokenParserBase,
+        },
+    };
+
+    static RELEASE: AtomicBool = AtomicBool::new(false);
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+    /// stalls on the first message until released
+    extern "C" fn stalled(_context: *const c_void, _message: *const c_char) {
+        while !RELEASE.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        CALLED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_stalled_callback() {
+        let callback = CallbackInfo::new(
+            SubscribeCode::Tokenizer,
+            SubscriptionType::JSON,
+            0,
+            std::ptr::null::<()>().into(),
+            Some(CallbackFn::Text(stalled)),
+        )
+        .with_delivery(Delivery { capacity: 4, ..Default::default() });
+        let (callback, dispatcher) = callback.dispatched();
+        let dispatcher = dispatcher.unwrap();
+        let queue = Arc::clone(dispatcher.get_queue());
+        drop(dispatcher);
+
+        let mut wrapper = CallbackWrapper::<TokenParserBase>::default();
+        wrapper.add_callback(callback);
+
+        // the first message is taken by the callback, which then stalls
+        wrapper.parse_token(Time::default(), &Token::DoorOpen);
+        while queue.peek_len() > 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let start = Instant::now();
+        for _ in 0..999 {
+            wrapper.parse_token(Time::default(), &Token::DoorOpen);
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // only the 4 newest wait in the queue
+        assert_eq!(queue.dropped(), 995);
+
+        // removing the callback drops the messages left and waits for the one being delivered
+        let release = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            RELEASE.store(true, Ordering::Relaxed);
+        });
+        let start = Instant::now();
+        drop(wrapper);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(CALLED.load(Ordering::Relaxed), 1);
+        assert_eq!(queue.dropped(), 999);
+        release.join().unwrap();
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(CALLED.load(Ordering::Relaxed), 1);
+    }
+}
@@ -1,9 +1,13 @@
-use std::ffi::CString;
-
-use num_enum::{FromPrimitive, IntoPrimitive};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde::Serialize;
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, IntoPrimitive, FromPrimitive)]
+use crate::dll_exports::{
+    csv_record::{self, CsvRecord},
+    error::GlrError,
+    xml,
+};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum SubscribeCode {
     #[default]
@@ -11,34 +15,161 @@ pub enum SubscribeCode {
     RunInfo = 2,
     Mapper = 3,
     SeedIndexer = 4,
+    SeedTrace = 5,
+}
+
+impl SubscribeCode {
+    pub fn from_u8(code: u8) -> Result<SubscribeCode, GlrError> {
+        SubscribeCode::try_from(code).map_err(|_| GlrError::InvalidSubscribeCode(code))
+    }
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, IntoPrimitive, FromPrimitive)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum SubscriptionType {
     #[default]
     JSON = 1,
     BITDATA = 2,
     CSV = 3,
+    XML = 4,
+    MSGPACK = 5,
 }
 
+/// set on the `message_type` given to the DLL to wrap every message in an `Envelope`
+pub const ENVELOPE_FLAG: u8 = 0x80;
+
 impl SubscriptionType {
-    pub fn convert<O>(&self, data: &O) -> Option<CString>
+    /// splits a `message_type` given to the DLL into the format and whether it has `ENVELOPE_FLAG`
+    pub fn from_flags(message_type: u8) -> Result<(SubscriptionType, bool), GlrError> {
+        let format = SubscriptionType::try_from(message_type & !ENVELOPE_FLAG)
+            .map_err(|_| GlrError::InvalidMessageType(message_type))?;
+
+        Ok((format, message_type & ENVELOPE_FLAG != 0))
+    }
+
+    /// binary formats can contain zero bytes so they are given to a
+    /// `BinaryEventCallback` together with their length
+    pub fn is_binary(&self) -> bool {
+        matches!(self, SubscriptionType::BITDATA | SubscriptionType::MSGPACK)
+    }
+
+    /// the message as it is given to the callback. Text formats end with a
+    /// nul byte, binary formats are returned as is.
+    pub fn convert<O>(&self, data: &O) -> Option<Vec<u8>>
     where
-        O: Serialize,
+        O: Serialize + CsvRecord,
     {
         match self {
             SubscriptionType::JSON => {
-                return serde_json::to_string(data)
-                    .ok()
-                    .map(|v| CString::new(v).ok())
-                    .flatten();
+                let mut json = serde_json::to_vec(data).ok()?;
+                // serde_json escapes control characters so this can't cut the message short
+                json.push(0);
+                Some(json)
+            }
+            SubscriptionType::BITDATA => bincode::serialize(data).ok(),
+            SubscriptionType::CSV => {
+                let mut csv = csv_record::to_csv(data).into_bytes();
+                csv.push(0);
+                Some(csv)
             }
-            SubscriptionType::BITDATA => {
-                let arr = bincode::serialize(data).ok()?;
-                return CString::from_vec_with_nul(arr).ok();
+            SubscriptionType::XML => {
+                let mut xml = xml::to_xml(data)?.into_bytes();
+                xml.push(0);
+                Some(xml)
             }
-            SubscriptionType::CSV => todo!(),
+            // maps with field names instead of arrays so it can be read without knowing the structs
+            SubscriptionType::MSGPACK => rmp_serde::to_vec_named(data).ok(),
+        }
+    }
+
+    /// the message sent before any data when subscribing, only CSV has one
+    pub fn header<O: CsvRecord>(&self) -> Option<Vec<u8>> {
+        match self {
+            SubscriptionType::CSV => {
+                let mut header = csv_record::header_row::<O>().into_bytes();
+                header.push(0);
+                Some(header)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use glr_core::{
+        location::{ItemIdentifier, Location},
+        run::TimedRun,
+        run_gen_result::RunGeneratorResult,
+        seed_indexer_result::OutputSeedIndexer,
+        split::NamedSplit,
+        time::Time,
+        token::Token,
+    };
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::{
+        dll_exports::xml::tests::from_xml,
+        seed_gen::{consumers::base_consumer::Consumer, test_levels, unity_random::UnityRandom},
+    };
+
+    /// decodes the XML and MessagePack messages and checks they give back the JSON output
+    fn check_formats<T: Serialize + DeserializeOwned + CsvRecord>(value: &T) {
+        let mut json = SubscriptionType::JSON.convert(value).unwrap();
+        assert_eq!(json.pop(), Some(0));
+        let json = String::from_utf8(json).unwrap();
+
+        let mut xml = SubscriptionType::XML.convert(value).unwrap();
+        assert_eq!(xml.pop(), Some(0));
+        let xml = from_xml(std::str::from_utf8(&xml).unwrap());
+        let from_xml: T = serde_json::from_value(xml).unwrap();
+        assert_eq!(serde_json::to_string(&from_xml).unwrap(), json);
+
+        let msgpack = SubscriptionType::MSGPACK.convert(value).unwrap();
+        let from_msgpack: T = rmp_serde::from_slice(&msgpack).unwrap();
+        assert_eq!(serde_json::to_string(&from_msgpack).unwrap(), json);
+    }
+
+    #[test]
+    fn test_xml_and_msgpack_match_json() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+
+        for token in [
+            Token::GeneratingLevel,
+            Token::PlayerJoinedLobby("<a & \"b\">".to_owned()),
+            Token::TimeSessionStart(time),
+            Token::SelectExpedition("R2C1".parse().unwrap(), -12),
+            Token::ItemAllocated("KEY_PURPLE_123".try_into().unwrap()),
+        ] {
+            check_formats(&token);
+        }
+
+        let mut run = TimedRun::new("R1A1".parse().unwrap(), vec!["a".to_owned()], time);
+        run.add_split(NamedSplit::new(Time::from_stamp(1234), "ZONE_50".to_owned()));
+        for result in [
+            RunGeneratorResult::GameStarted("R1A1".parse().unwrap(), 4, time),
+            RunGeneratorResult::CheckpointUsed,
+            RunGeneratorResult::LevelRun(run),
+        ] {
+            check_formats(&result);
+        }
+
+        for location in [
+            Location::Gatherable(ItemIdentifier::Unknown(7), 50, 2),
+            Location::BigCollectable(ItemIdentifier::Cell, 51),
+            Location::GenerationStarted("R1A1".to_owned()),
+        ] {
+            check_formats(&location);
+        }
+
+        let levels = test_levels();
+        let mut layout = vec![OutputSeedIndexer::ZoneResourceSummary(0, 50, Vec::new())];
+        levels["R1A1"].take(&mut UnityRandom::from(1), &mut layout);
+
+        for item in &layout {
+            check_formats(item);
         }
     }
 }
@@ -0,0 +1,128 @@
+use std::{cell::RefCell, fmt::Display, path::PathBuf};
+
+/// returned by the exported functions when they succeed, errors are negative
+pub const GLR_OK: i32 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlrError {
+    /// the name of the argument that was null
+    NullPointer(&'static str),
+    InvalidSubscribeCode(u8),
+    InvalidMessageType(u8),
+    PathNotFound(PathBuf),
+    /// the files that could not be read, the others were still processed
+    ReadFailed(Vec<PathBuf>),
+    InvalidLevel(String),
+    UnknownQueue(u32),
+    /// the size of the message and of the buffer given
+    BufferTooSmall(usize, usize),
+    LevelOverrides(String),
+    UnknownJob(u32),
+    InvalidFilter(String),
+    UnknownListener(u32),
+    /// the code and channel id
+    UnknownCallback(u8, u32),
+    InvalidOverflowPolicy(u8),
+    /// the name of a level that parses but has no level descriptor
+    NoDescriptor(String),
+}
+
+impl GlrError {
+    /// the status code returned by the exported function
+    pub fn status(&self) -> i32 {
+        match self {
+            GlrError::NullPointer(_) => -1,
+            GlrError::InvalidSubscribeCode(_) => -2,
+            GlrError::InvalidMessageType(_) => -3,
+            GlrError::PathNotFound(_) => -4,
+            GlrError::ReadFailed(_) => -5,
+            GlrError::InvalidLevel(_) => -6,
+            GlrError::UnknownQueue(_) => -7,
+            GlrError::BufferTooSmall(..) => -8,
+            GlrError::LevelOverrides(_) => -9,
+            GlrError::UnknownJob(_) => -10,
+            GlrError::InvalidFilter(_) => -11,
+            GlrError::UnknownListener(_) => -12,
+            GlrError::UnknownCallback(..) => -13,
+            GlrError::InvalidOverflowPolicy(_) => -14,
+            GlrError::NoDescriptor(_) => -15,
+        }
+    }
+
+    /// saves the error for `last_error` and returns its status code
+    pub fn into_status(self) -> i32 {
+        let status = self.status();
+        LAST_ERROR.with(|e| *e.borrow_mut() = Some(self.to_string()));
+        status
+    }
+}
+
+impl Display for GlrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlrError::NullPointer(name) => write!(f, "{name} is null"),
+            GlrError::InvalidSubscribeCode(code) => write!(f, "{code} is not a subscribe code"),
+            GlrError::InvalidMessageType(message_type) => write!(f, "{message_type} is not a message type"),
+            GlrError::PathNotFound(path) => write!(f, "{} does not exist", path.display()),
+            GlrError::ReadFailed(paths) => {
+                let paths: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "could not read {}", paths.join(", "))
+            }
+            GlrError::InvalidLevel(level) => write!(f, "{level} is not a level name"),
+            GlrError::UnknownQueue(queue) => write!(f, "there is no queue {queue}"),
+            GlrError::BufferTooSmall(needed, len) => {
+                write!(f, "the message is {needed} bytes but the buffer is only {len}")
+            }
+            GlrError::LevelOverrides(e) => write!(f, "{e}"),
+            GlrError::UnknownJob(job) => write!(f, "there is no job {job}"),
+            GlrError::InvalidFilter(e) => write!(f, "invalid filter: {e}"),
+            GlrError::UnknownListener(listener) => write!(f, "there is no listener {listener}"),
+            GlrError::UnknownCallback(code, channel_id) => {
+                write!(f, "there is no callback with code {code} and channel id {channel_id}")
+            }
+            GlrError::InvalidOverflowPolicy(policy) => write!(f, "{policy} is not an overflow policy"),
+            GlrError::NoDescriptor(level) => write!(f, "there is no level descriptor for {level}"),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// the message of the last error on this thread, it is not cleared when a function succeeds
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.with(|e| e.borrow().clone())
+}
+
+/// the status code of `result`, saving the error for `last_error`
+pub fn to_status<T>(result: Result<T, GlrError>, ok: impl FnOnce(T) -> i32) -> i32 {
+    match result {
+        Ok(v) => ok(v),
+        Err(e) => e.into_status(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dll_exports::enums::{SubscribeCode, SubscriptionType};
+
+    #[test]
+    fn test_status() {
+        assert_eq!(SubscribeCode::from_u8(1), Ok(SubscribeCode::Tokenizer));
+        assert_eq!(SubscriptionType::from_flags(0x83), Ok((SubscriptionType::CSV, true)));
+
+        let status = to_status(SubscribeCode::from_u8(0), |_| GLR_OK);
+        assert_eq!(status, -2);
+        assert_eq!(last_error().as_deref(), Some("0 is not a subscribe code"));
+
+        let status = to_status(SubscriptionType::from_flags(0x86), |_| GLR_OK);
+        assert_eq!(status, -3);
+        assert_eq!(last_error().as_deref(), Some("134 is not a message type"));
+
+        // succeeding keeps the last error
+        assert_eq!(to_status(Ok(5usize), |v| v as i32), 5);
+        assert_eq!(last_error().as_deref(), Some("134 is not a message type"));
+    }
+}
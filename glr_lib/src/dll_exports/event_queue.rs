@@ -0,0 +1,250 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+/// what `push` does when a bounded queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum OverflowPolicy {
+    /// waits until there is room, slowing down whoever pushes
+    Block = 0,
+    /// drops the oldest message to make room
+    #[default]
+    DropOldest = 1,
+    /// drops the message being pushed
+    DropNewest = 2,
+}
+
+/// messages of a subscription kept until they are polled or dispatched
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    state: Mutex<QueueState>,
+    ready: Condvar,
+    not_full: Condvar,
+
+    /// unbounded if None
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    messages: VecDeque<Vec<u8>>,
+    closed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollResult {
+    Empty,
+    /// the message was removed from the queue and this many bytes were written
+    Copied(usize),
+    /// the message is this long and was left in the queue
+    TooSmall(usize),
+}
+
+impl EventQueue {
+    pub fn bounded(capacity: usize, policy: OverflowPolicy) -> EventQueue {
+        EventQueue {
+            capacity: Some(capacity.max(1)),
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&self, message: Vec<u8>) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let is_full = |state: &QueueState| self.capacity.is_some_and(|capacity| state.messages.len() >= capacity);
+
+        if is_full(&state) {
+            match self.policy {
+                OverflowPolicy::Block => {
+                    let Ok(waited) = self.not_full.wait_while(state, |state| is_full(state) && !state.closed) else {
+                        return;
+                    };
+                    state = waited;
+                }
+                OverflowPolicy::DropOldest => {
+                    state.messages.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+        if state.closed {
+            return;
+        }
+
+        state.messages.push_back(message);
+        self.ready.notify_all();
+    }
+
+    /// how many messages the overflow policy dropped, plus the ones `close` dropped
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// moves the oldest message into `buf` if it fits
+    pub fn pop_into(&self, buf: &mut [u8]) -> PollResult {
+        let Ok(mut state) = self.state.lock() else {
+            return PollResult::Empty;
+        };
+
+        match state.messages.front() {
+            None => PollResult::Empty,
+            Some(message) if message.len() > buf.len() => PollResult::TooSmall(message.len()),
+            Some(_) => {
+                let message = state.messages.pop_front().unwrap_or_default();
+                self.not_full.notify_all();
+                buf[..message.len()].copy_from_slice(&message);
+                PollResult::Copied(message.len())
+            }
+        }
+    }
+
+    /// blocks until there is a message and removes it. Returns None once the
+    /// queue is closed or finished and empty.
+    pub fn pop_wait(&self) -> Option<Vec<u8>> {
+        let state = self.state.lock().ok()?;
+        let mut state = self
+            .ready
+            .wait_while(state, |state| state.messages.is_empty() && !state.closed)
+            .ok()?;
+
+        let message = state.messages.pop_front()?;
+        self.not_full.notify_all();
+        Some(message)
+    }
+
+    /// the length of the oldest message, 0 if there is none
+    pub fn peek_len(&self) -> usize {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| state.messages.front().map(Vec::len))
+            .unwrap_or_default()
+    }
+
+    /// blocks until there is a message or the timeout runs out. Returns whether there is a message.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let Ok(state) = self.state.lock() else {
+            return false;
+        };
+
+        self.ready
+            .wait_timeout_while(state, timeout, |state| state.messages.is_empty() && !state.closed)
+            .map(|(state, _)| !state.messages.is_empty())
+            .unwrap_or(false)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().map(|state| state.closed).unwrap_or(true)
+    }
+
+    /// nothing can be pushed after, but the messages left can still be taken
+    pub fn finish(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+        }
+        self.ready.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// drops every message, counting them in `dropped`, and wakes up anyone
+    /// waiting. Nothing can be pushed after.
+    pub fn close(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+            self.dropped.fetch_add(state.messages.len() as u64, Ordering::Relaxed);
+            state.messages.clear();
+        }
+        self.ready.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_event_queue() {
+        let queue = Arc::new(EventQueue::default());
+        let mut buf = [0u8; 4];
+
+        assert_eq!(queue.pop_into(&mut buf), PollResult::Empty);
+        assert!(!queue.wait(Duration::from_millis(1)));
+
+        queue.push(b"hello\0".to_vec());
+        queue.push(b"ab".to_vec());
+        assert!(queue.wait(Duration::ZERO));
+        assert_eq!(queue.pop_into(&mut buf), PollResult::TooSmall(6));
+        assert_eq!(queue.peek_len(), 6);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(queue.pop_into(&mut buf), PollResult::Copied(6));
+        assert_eq!(&buf[..6], b"hello\0");
+        assert_eq!(queue.pop_into(&mut buf), PollResult::Copied(2));
+        assert_eq!(queue.pop_into(&mut buf), PollResult::Empty);
+
+        // a message pushed from another thread wakes up the wait
+        let pusher = Arc::clone(&queue);
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            pusher.push(vec![1]);
+        });
+        assert!(queue.wait(Duration::from_secs(5)));
+        handle.join().unwrap();
+
+        // closing stops waiting right away
+        queue.close();
+        let start = Instant::now();
+        assert!(!queue.wait(Duration::from_secs(5)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        queue.push(vec![2]);
+        assert_eq!(queue.pop_into(&mut buf), PollResult::Empty);
+    }
+
+    #[test]
+    fn test_overflow_policy() {
+        let drop_oldest = EventQueue::bounded(2, OverflowPolicy::DropOldest);
+        let drop_newest = EventQueue::bounded(2, OverflowPolicy::DropNewest);
+        for i in 0..4 {
+            drop_oldest.push(vec![i]);
+            drop_newest.push(vec![i]);
+        }
+        assert_eq!(drop_oldest.pop_wait(), Some(vec![2]));
+        assert_eq!(drop_oldest.dropped(), 2);
+        assert_eq!(drop_newest.pop_wait(), Some(vec![0]));
+        assert_eq!(drop_newest.dropped(), 2);
+
+        // a full blocking queue waits for a message to be taken
+        let queue = Arc::new(EventQueue::bounded(1, OverflowPolicy::Block));
+        queue.push(vec![0]);
+        let pusher = Arc::clone(&queue);
+        let handle = thread::spawn(move || pusher.push(vec![1]));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(queue.peek_len(), 1);
+        assert_eq!(queue.pop_wait(), Some(vec![0]));
+        handle.join().unwrap();
+        assert_eq!(queue.pop_wait(), Some(vec![1]));
+        assert_eq!(queue.dropped(), 0);
+
+        queue.close();
+        assert_eq!(queue.pop_wait(), None);
+    }
+}
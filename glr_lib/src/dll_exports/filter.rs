@@ -0,0 +1,234 @@
+//! filters the events a subscription gets by the name of their variant
+//! (`SessionSeed`, `LevelRun`, `Key`...), before they are serialized.
+//!
+//! A filter is a list of names separated by `,` or `|`. Names starting with
+//! `!` are excluded, the others are the only ones let through, e.g.
+//! `BadPacketSentByPlayer` or `!ItemAllocated,!ItemSpawn`.
+
+use std::{cell::Cell, collections::HashSet, fmt::Display};
+
+use serde::{
+    Deserializer, Serialize, Serializer,
+    de::{DeserializeOwned, Visitor, value::Error},
+    forward_to_deserialize_any,
+    ser::Impossible,
+};
+
+use crate::dll_exports::error::GlrError;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    include: HashSet<&'static str>,
+    exclude: HashSet<&'static str>,
+}
+
+impl EventFilter {
+    /// parses `text` checking that every name is a variant of `O`
+    pub fn parse<O: DeserializeOwned>(text: &str) -> Result<EventFilter, GlrError> {
+        let variants = variant_names::<O>();
+        let mut filter = EventFilter::default();
+
+        for name in text.split([',', '|']).map(str::trim).filter(|name| !name.is_empty()) {
+            let (set, name) = match name.strip_prefix('!') {
+                Some(name) => (&mut filter.exclude, name.trim()),
+                None => (&mut filter.include, name),
+            };
+
+            let variant = variants
+                .iter()
+                .find(|variant| **variant == name)
+                .ok_or_else(|| GlrError::InvalidFilter(format!("{name} is not one of {}", variants.join(", "))))?;
+            set.insert(variant);
+        }
+
+        Ok(filter)
+    }
+
+    pub fn accepts(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.contains(name)) && !self.exclude.contains(name)
+    }
+}
+
+/// the names of the variants of the enum `T`, empty if it isn't one
+pub fn variant_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    let deserializer = VariantNames::default();
+    let _ = T::deserialize(&deserializer);
+    deserializer.0.get()
+}
+
+/// the name of the variant `value` is, without serializing its fields
+pub fn variant_name<T: Serialize + ?Sized>(value: &T) -> Option<&'static str> {
+    let serializer = VariantName::default();
+    let _ = value.serialize(&serializer);
+    serializer.0.get()
+}
+
+#[derive(Default)]
+struct VariantNames(Cell<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for &VariantNames {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(serde::de::Error::custom("not an enum"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.set(variants);
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// stops at the first thing it is given, keeping its name if it's a variant
+#[derive(Default)]
+struct VariantName(Cell<Option<&'static str>>);
+
+#[derive(Debug)]
+struct Stop;
+
+impl Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stop")
+    }
+}
+
+impl std::error::Error for Stop {}
+
+impl serde::ser::Error for Stop {
+    fn custom<T: Display>(_msg: T) -> Self {
+        Stop
+    }
+}
+
+macro_rules! stop {
+    ($($method:ident($($ty:ty),*)),* $(,)?) => {
+        $(fn $method(self, $(_: $ty),*) -> Result<(), Stop> {
+            Err(Stop)
+        })*
+    };
+}
+
+impl Serializer for &VariantName {
+    type Ok = ();
+    type Error = Stop;
+    type SerializeSeq = Impossible<(), Stop>;
+    type SerializeTuple = Impossible<(), Stop>;
+    type SerializeTupleStruct = Impossible<(), Stop>;
+    type SerializeTupleVariant = Impossible<(), Stop>;
+    type SerializeMap = Impossible<(), Stop>;
+    type SerializeStruct = Impossible<(), Stop>;
+    type SerializeStructVariant = Impossible<(), Stop>;
+
+    stop!(
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64),
+        serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64), serialize_f32(f32),
+        serialize_f64(f64), serialize_char(char), serialize_str(&str), serialize_bytes(&[u8]),
+        serialize_none(), serialize_unit(), serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), Stop> {
+        self.0.set(Some(variant));
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, _value: &T) -> Result<(), Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Stop> {
+        self.0.set(Some(variant));
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Stop> {
+        self.0.set(Some(variant));
+        Err(Stop)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Stop> {
+        Err(Stop)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Stop> {
+        self.0.set(Some(variant));
+        Err(Stop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glr_core::{data::LevelDescriptor, location::Location, token::Token};
+
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        assert!(variant_names::<Token>().contains(&"BadPacketSentByPlayer"));
+        assert_eq!(variant_name(&Token::DoorOpen), Some("DoorOpen"));
+        assert_eq!(variant_name(&Token::SessionSeed(5)), Some("SessionSeed"));
+        assert_eq!(variant_name(&Token::SelectExpedition(LevelDescriptor::default(), 1)), Some("SelectExpedition"));
+        assert_eq!(variant_name(&Location::Generator(1, 2)), Some("Generator"));
+        assert_eq!(variant_name(&5), None);
+
+        let filter = EventFilter::parse::<Token>("BadPacketSentByPlayer | SessionSeed").unwrap();
+        assert!(filter.accepts("SessionSeed"));
+        assert!(!filter.accepts("DoorOpen"));
+
+        let filter = EventFilter::parse::<Token>("!ItemAllocated, !ItemSpawn").unwrap();
+        assert!(filter.accepts("DoorOpen"));
+        assert!(!filter.accepts("ItemSpawn"));
+
+        assert!(EventFilter::parse::<Token>("").unwrap().accepts("DoorOpen"));
+        assert!(matches!(EventFilter::parse::<Token>("DoorOpened"), Err(GlrError::InvalidFilter(_))));
+        assert!(EventFilter::parse::<Location>("DoorOpen").is_err());
+    }
+}
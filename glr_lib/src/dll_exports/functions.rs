@@ -1,60 +1,341 @@
 use std::{
+    collections::HashMap,
     path::PathBuf,
-    sync::{Mutex, OnceLock},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicU32, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 
 use glr_core::{data::LevelDescriptor, time::Time, token::Token};
 
-use crate::{core::token_parser::TokenParser, dll_exports::{
-    callback_handler::CallbackWrapper, enums::SubscribeCode, structs::{CallbackInfo, MainThread}, token_parsers::{TokenParserInner, token_parser_base::TokenParserBase, token_parser_locations::TokenParserLocations, token_parser_runs::TokenParserRuns, token_parser_seeds::TokenParserSeed}
+use crate::{core::token_parser::TokenParser, load_seed_consumers::LoadSeedConsumers, seed_gen::levels::LevelDescriptors, dll_exports::{
+    callback_handler::CallbackWrapper, dispatcher::Delivery, enums::{SubscribeCode, SubscriptionType}, error::GlrError, filter::EventFilter, event_queue::{EventQueue, PollResult}, jobs::{Job, JobProgress, ProgressCallback}, structs::{CallbackFn, CallbackInfo, MainThread, ThreadSafePtr}, token_parsers::{TokenParserInner, token_parser_base::TokenParserBase, token_parser_locations::TokenParserLocations, token_parser_runs::TokenParserRuns, token_parser_seeds::TokenParserSeed, token_parser_seed_trace::TokenParserSeedTrace}
 }};
 
-static MAIN_THREAD: OnceLock<Mutex<Option<MainThread>>> = OnceLock::new();
+/// the listener used by the functions that don't take one, started by `start_listener` or the first callback added to it
+pub const DEFAULT_LISTENER: u32 = 0;
 
-pub fn start_listener(file_path: PathBuf) {
-    let _ = MAIN_THREAD
-        .get_or_init(|| Some(MainThread::create(None)).into())
+static LISTENERS: OnceLock<Mutex<HashMap<u32, MainThread>>> = OnceLock::new();
+static NEXT_LISTENER: AtomicU32 = AtomicU32::new(1);
+
+/// queues get the channel ids with this bit set so they don't collide with callbacks
+pub const QUEUE_CHANNEL_FLAG: u32 = 0x8000_0000;
+
+/// queue id to the listener and code it is subscribed to and the queue
+type Queues = HashMap<u32, (u32, SubscribeCode, Arc<EventQueue>)>;
+
+static QUEUES: OnceLock<Mutex<Queues>> = OnceLock::new();
+static NEXT_QUEUE: AtomicU32 = AtomicU32::new(1);
+
+type Jobs = HashMap<u32, (Arc<Job>, JoinHandle<()>)>;
+
+static JOBS: OnceLock<Mutex<Jobs>> = OnceLock::new();
+static NEXT_JOB: AtomicU32 = AtomicU32::new(1);
+
+fn get_queue(queue_id: u32) -> Option<Arc<EventQueue>> {
+    QUEUES.get()?.lock().ok()?.get(&queue_id).map(|(_, _, queue)| Arc::clone(queue))
+}
+
+/// runs `f` on the listener if it is running
+fn with_listener<T>(listener: u32, f: impl FnOnce(&mut MainThread) -> T) -> Result<T, GlrError> {
+    LISTENERS
+        .get()
+        .and_then(|v| v.lock().ok()?.get_mut(&listener).map(f))
+        .ok_or(GlrError::UnknownListener(listener))
+}
+
+/// points the default listener to `file_path`, starting it if it isn't running
+/// (the first call or after `shutdown_all`)
+pub fn start_listener(file_path: PathBuf) -> Result<(), GlrError> {
+    if !file_path.is_dir() {
+        return Err(GlrError::PathNotFound(file_path));
+    }
+
+    LISTENERS
+        .get_or_init(Default::default)
+        .lock()
+        .map_err(|_| GlrError::UnknownListener(DEFAULT_LISTENER))?
+        .entry(DEFAULT_LISTENER)
+        .or_insert_with(|| MainThread::create(None))
+        .change_logs_folder(file_path);
+
+    Ok(())
+}
+
+/// starts a new listener reading the logs in `file_path`, with its own
+/// callbacks and parsers. Returns its id, which is never `DEFAULT_LISTENER`
+/// or the id of a listener that is still running.
+pub fn create_listener(file_path: PathBuf) -> Result<u32, GlrError> {
+    if !file_path.is_dir() {
+        return Err(GlrError::PathNotFound(file_path));
+    }
+
+    let mut listeners = LISTENERS
+        .get_or_init(Default::default)
         .lock()
-        .map(|mut v| match v.as_mut() {
-            Some(m_th) => m_th.change_logs_folder(file_path),
-            None => {
-                *v = Some(MainThread::create(Some(file_path)));
+        .map_err(|_| GlrError::UnknownListener(DEFAULT_LISTENER))?;
+
+    // the ids have to fit in the positive i32 the dll returns, after they wrap
+    // around the ones that are still in use are skipped
+    let listener = loop {
+        let listener = NEXT_LISTENER.fetch_add(1, Ordering::Relaxed) & i32::MAX as u32;
+        if listener != DEFAULT_LISTENER && !listeners.contains_key(&listener) {
+            break listener;
+        }
+    };
+
+    // giving the path after creating it makes the folder be checked right away
+    let main_thread = MainThread::create(None);
+    main_thread.change_logs_folder(file_path);
+    listeners.insert(listener, main_thread);
+
+    Ok(listener)
+}
+
+pub fn set_listener_folder(listener: u32, file_path: PathBuf) -> Result<(), GlrError> {
+    if !file_path.is_dir() {
+        return Err(GlrError::PathNotFound(file_path));
+    }
+
+    with_listener(listener, |m_th| m_th.change_logs_folder(file_path))
+}
+
+/// stops the listener and closes the queues subscribed to it
+pub fn destroy_listener(listener: u32) -> Result<(), GlrError> {
+    let main_thread = LISTENERS
+        .get()
+        .and_then(|v| v.lock().ok()?.remove(&listener))
+        .ok_or(GlrError::UnknownListener(listener))?;
+    drop(main_thread);
+
+    if let Some(mut queues) = QUEUES.get().and_then(|v| v.lock().ok()) {
+        queues.retain(|_, (queue_listener, _, queue)| {
+            if *queue_listener == listener {
+                queue.close();
             }
+            *queue_listener != listener
         });
+    }
+
+    Ok(())
 }
 
-pub fn add_callback(callback: CallbackInfo) {
-    let _ = MAIN_THREAD
-        .get_or_init(|| Some(MainThread::create(None)).into())
+/// like `with_listener`, but starts the default listener without a folder if
+/// it isn't running, so callbacks can be added before `start_listener`
+fn with_listener_or_default<T>(listener: u32, f: impl FnOnce(&mut MainThread) -> T) -> Result<T, GlrError> {
+    if listener != DEFAULT_LISTENER {
+        return with_listener(listener, f);
+    }
+
+    LISTENERS
+        .get_or_init(Default::default)
         .lock()
-        .map(|mut v| v.as_mut().map(|v| v.register_callback(callback)));
+        .map(|mut v| f(v.entry(DEFAULT_LISTENER).or_insert_with(|| MainThread::create(None))))
+        .map_err(|_| GlrError::UnknownListener(DEFAULT_LISTENER))
 }
 
-pub fn remove_callback(code: SubscribeCode, channel_id: u32) {
-    let _ = MAIN_THREAD
-        .get_or_init(|| Some(MainThread::create(None)).into())
-        .lock()
-        .map(|mut v| v.as_mut().map(|v| v.remove_callback(code, channel_id)));
+pub fn add_callback(listener: u32, callback: CallbackInfo) -> Result<(), GlrError> {
+    with_listener_or_default(listener, |m_th| m_th.register_callback(callback))
+}
+
+pub fn remove_callback(listener: u32, code: SubscribeCode, channel_id: u32) -> Result<(), GlrError> {
+    with_listener_or_default(listener, |m_th| m_th.remove_callback(code, channel_id))
 }
 
+/// how many messages the overflow policy of the callback dropped
+pub fn dropped_events(listener: u32, code: SubscribeCode, channel_id: u32) -> Result<u64, GlrError> {
+    with_listener(listener, |m_th| m_th.dropped_events(code, channel_id))?
+        .ok_or(GlrError::UnknownCallback(code.into(), channel_id))
+}
+
+/// stops every listener, queue and job
 pub fn shutdown_all() {
-    MAIN_THREAD
+    let listeners: Vec<_> = LISTENERS
         .get()
-        .map(|v| v.lock().ok())
-        .flatten()
-        .as_mut()
-        .map(|v| v.take());
+        .and_then(|v| v.lock().ok())
+        .map(|mut listeners| listeners.drain().collect())
+        .unwrap_or_default();
+    drop(listeners);
+
+    if let Some(mut queues) = QUEUES.get().and_then(|v| v.lock().ok()) {
+        queues.drain().for_each(|(_, (_, _, queue))| queue.close());
+    }
+
+    let jobs: Vec<_> = JOBS
+        .get()
+        .and_then(|v| v.lock().ok())
+        .map(|mut jobs| jobs.drain().map(|(_, job)| job).collect())
+        .unwrap_or_default();
+    for (job, handle) in jobs {
+        job.cancel();
+        let _ = handle.join();
+    }
 }
 
-pub fn process_paths(paths: Vec<PathBuf>, callback: CallbackInfo) {
+/// subscribes a queue to `code` of the listener that keeps the messages until
+/// they are polled, up to `delivery.capacity` of them. Returns the id of the
+/// queue, which is never 0.
+pub fn create_queue(
+    listener: u32,
+    code: SubscribeCode,
+    message_type: SubscriptionType,
+    envelope: bool,
+    delivery: Delivery,
+) -> Result<u32, GlrError> {
+    let queue_id = NEXT_QUEUE.fetch_add(1, Ordering::Relaxed) & !QUEUE_CHANNEL_FLAG;
+    let queue = Arc::new(EventQueue::bounded(delivery.capacity, delivery.policy));
+
+    add_callback(
+        listener,
+        CallbackInfo::new(
+            code,
+            message_type,
+            queue_id | QUEUE_CHANNEL_FLAG,
+            std::ptr::null::<()>().into(),
+            Some(CallbackFn::Queue(Arc::clone(&queue))),
+        )
+        .with_envelope(envelope),
+    )?;
+
+    if let Ok(mut queues) = QUEUES.get_or_init(Default::default).lock() {
+        queues.insert(queue_id, (listener, code, queue));
+    }
+
+    Ok(queue_id)
+}
+
+/// moves the oldest message of the queue into `buf` and returns its length, 0 if there is none
+pub fn poll_event(queue_id: u32, buf: &mut [u8]) -> Result<usize, GlrError> {
+    let queue = get_queue(queue_id).ok_or(GlrError::UnknownQueue(queue_id))?;
+
+    match queue.pop_into(buf) {
+        PollResult::Empty => Ok(0),
+        PollResult::Copied(len) => Ok(len),
+        PollResult::TooSmall(len) => Err(GlrError::BufferTooSmall(len, buf.len())),
+    }
+}
+
+/// the length of the message `poll_event` would return, 0 if there is none
+pub fn peek_event_len(queue_id: u32) -> Result<usize, GlrError> {
+    get_queue(queue_id)
+        .map(|queue| queue.peek_len())
+        .ok_or(GlrError::UnknownQueue(queue_id))
+}
+
+/// whether the queue has a message before the timeout runs out
+pub fn wait_event(queue_id: u32, timeout: Duration) -> Result<bool, GlrError> {
+    get_queue(queue_id)
+        .map(|queue| queue.wait(timeout))
+        .ok_or(GlrError::UnknownQueue(queue_id))
+}
+
+/// how many messages the overflow policy of the queue dropped
+pub fn queue_dropped_events(queue_id: u32) -> Result<u64, GlrError> {
+    get_queue(queue_id)
+        .map(|queue| queue.dropped())
+        .ok_or(GlrError::UnknownQueue(queue_id))
+}
+
+pub fn destroy_queue(queue_id: u32) -> Result<(), GlrError> {
+    let (listener, code, queue) = QUEUES
+        .get()
+        .and_then(|v| v.lock().ok()?.remove(&queue_id))
+        .ok_or(GlrError::UnknownQueue(queue_id))?;
+
+    queue.close();
+    remove_callback(listener, code, queue_id | QUEUE_CHANNEL_FLAG)
+}
+
+/// runs the parser of the callback's code over the paths, returning the ones that could not be read
+fn run_paths(paths: Vec<PathBuf>, callback: CallbackInfo, job: Option<&Job>) -> Vec<PathBuf> {
     match callback.get_code() {
-        SubscribeCode::Tokenizer => MainThread::static_run::<TokenParserBase>(paths, callback),
-        SubscribeCode::RunInfo => MainThread::static_run::<TokenParserRuns>(paths, callback),
-        SubscribeCode::Mapper => MainThread::static_run::<TokenParserLocations>(paths, callback),
-        SubscribeCode::SeedIndexer => MainThread::static_run::<TokenParserSeed>(paths, callback),
+        SubscribeCode::Tokenizer => MainThread::static_run::<TokenParserBase>(paths, callback, job),
+        SubscribeCode::RunInfo => MainThread::static_run::<TokenParserRuns>(paths, callback, job),
+        SubscribeCode::Mapper => MainThread::static_run::<TokenParserLocations>(paths, callback, job),
+        SubscribeCode::SeedIndexer => MainThread::static_run::<TokenParserSeed>(paths, callback, job),
+        SubscribeCode::SeedTrace => MainThread::static_run::<TokenParserSeedTrace>(paths, callback, job),
+    }
+}
+
+/// checks that `filter` only names events of `code`
+pub fn parse_filter(code: SubscribeCode, filter: &str) -> Result<EventFilter, GlrError> {
+    match code {
+        SubscribeCode::Tokenizer => EventFilter::parse::<<TokenParserBase as TokenParserInner>::Output>(filter),
+        SubscribeCode::RunInfo => EventFilter::parse::<<TokenParserRuns as TokenParserInner>::Output>(filter),
+        SubscribeCode::Mapper => EventFilter::parse::<<TokenParserLocations as TokenParserInner>::Output>(filter),
+        SubscribeCode::SeedIndexer => EventFilter::parse::<<TokenParserSeed as TokenParserInner>::Output>(filter),
+        SubscribeCode::SeedTrace => EventFilter::parse::<<TokenParserSeedTrace as TokenParserInner>::Output>(filter),
     }
 }
 
+/// processes every path it can read, the ones it couldn't are in the error
+pub fn process_paths(paths: Vec<PathBuf>, callback: CallbackInfo) -> Result<(), GlrError> {
+    let failed = run_paths(paths, callback, None);
+
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(GlrError::ReadFailed(failed)),
+    }
+}
+
+fn get_job(job_id: u32) -> Result<Arc<Job>, GlrError> {
+    JOBS.get()
+        .and_then(|v| v.lock().ok()?.get(&job_id).map(|(job, _)| Arc::clone(job)))
+        .ok_or(GlrError::UnknownJob(job_id))
+}
+
+/// `process_paths` on a new thread. The callbacks are called from that thread.
+/// Returns the id of the job, which is never 0.
+pub fn process_paths_async(
+    paths: Vec<PathBuf>,
+    callback: CallbackInfo,
+    progress_callback: Option<(ProgressCallback, ThreadSafePtr)>,
+) -> u32 {
+    let job_id = NEXT_JOB.fetch_add(1, Ordering::Relaxed).max(1);
+    let job = Arc::new(Job::new(job_id, paths.len() as u32, progress_callback));
+
+    let thread_job = Arc::clone(&job);
+    let handle = thread::spawn(move || {
+        run_paths(paths, callback, Some(&thread_job));
+        thread_job.finish();
+    });
+
+    if let Ok(mut jobs) = JOBS.get_or_init(Default::default).lock() {
+        jobs.insert(job_id, (job, handle));
+    }
+
+    job_id
+}
+
+pub fn job_progress(job_id: u32) -> Result<JobProgress, GlrError> {
+    get_job(job_id).map(|job| job.progress())
+}
+
+/// whether the job stopped before the timeout runs out
+pub fn wait_job(job_id: u32, timeout: Duration) -> Result<bool, GlrError> {
+    get_job(job_id).map(|job| job.wait(timeout))
+}
+
+/// the job stops before the next line, it stays around until `destroy_job`
+pub fn cancel_job(job_id: u32) -> Result<(), GlrError> {
+    get_job(job_id).map(|job| job.cancel())
+}
+
+/// cancels the job if it is still running and waits for its thread to stop
+pub fn destroy_job(job_id: u32) -> Result<(), GlrError> {
+    let (job, handle) = JOBS
+        .get()
+        .and_then(|v| v.lock().ok()?.remove(&job_id))
+        .ok_or(GlrError::UnknownJob(job_id))?;
+
+    job.cancel();
+    let _ = handle.join();
+    Ok(())
+}
+
 pub fn process_paths_collect<TP: TokenParserInner + Default>(paths: Vec<PathBuf>) -> Vec<TP::Output> {
     let mut result = Vec::new();
     
@@ -63,9 +344,110 @@ pub fn process_paths_collect<TP: TokenParserInner + Default>(paths: Vec<PathBuf>
     result
 }
 
-pub fn process_seed(level: LevelDescriptor, seed: i32, callback: CallbackInfo) {
+/// outputs the seed indexer result of `level` with `seed` to the callback, or
+/// returns `NoDescriptor` without calling it if `level` has no level descriptor
+pub fn process_seed(level: LevelDescriptor, seed: i32, callback: CallbackInfo) -> Result<(), GlrError> {
+    let level_descriptors = LevelDescriptors::default();
+    level_descriptors.refresh_overrides();
+    if level_descriptors.get_level(&level).is_none() {
+        return Err(GlrError::NoDescriptor(level.to_string()));
+    }
+
     let mut parser = CallbackWrapper::<TokenParserSeed>::default();
     
     parser.add_callback(callback);
     parser.parse_token(Time::default(), &Token::SelectExpedition(level, seed));
+    Ok(())
+}
+
+pub fn load_level_overrides(path: PathBuf) -> Result<usize, GlrError> {
+    LoadSeedConsumers::load_overrides(path).map_err(GlrError::LevelOverrides)
+}
+
+pub fn clear_level_overrides() {
+    LoadSeedConsumers::clear_overrides();
+}
+
+/// the error of the last time the override files changed and could not be loaded again
+pub fn level_overrides_status() -> Result<(), GlrError> {
+    match LoadSeedConsumers::reload_error() {
+        Some(e) => Err(GlrError::LevelOverrides(e)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// held by the tests that use the listeners, queues or jobs, `shutdown_all` stops all of them
+    pub(crate) static GLOBALS: Mutex<()> = Mutex::new(());
+
+    fn no_callback() -> CallbackInfo {
+        CallbackInfo::new(SubscribeCode::Tokenizer, SubscriptionType::JSON, 0, std::ptr::null::<()>().into(), None)
+    }
+
+    #[test]
+    fn test_listeners() {
+        let _globals = GLOBALS.lock().unwrap_or_else(|e| e.into_inner());
+        let folder = std::env::temp_dir();
+        let first = create_listener(folder.clone()).unwrap();
+        let second = create_listener(folder.clone()).unwrap();
+        assert!(first != DEFAULT_LISTENER && first != second);
+        assert_eq!(create_listener(folder.join("glr_missing_folder")), Err(GlrError::PathNotFound(folder.join("glr_missing_folder"))));
+
+        let queue = create_queue(first, SubscribeCode::Tokenizer, SubscriptionType::JSON, false, Delivery::default()).unwrap();
+        assert_eq!(wait_event(queue, Duration::ZERO), Ok(false));
+        assert_eq!(queue_dropped_events(queue), Ok(0));
+
+        // destroying a listener leaves the others running and closes its queues
+        assert_eq!(destroy_listener(first), Ok(()));
+        assert_eq!(destroy_listener(first), Err(GlrError::UnknownListener(first)));
+        assert_eq!(wait_event(queue, Duration::ZERO), Err(GlrError::UnknownQueue(queue)));
+        assert!(add_callback(first, no_callback()).is_err());
+        assert_eq!(set_listener_folder(second, folder.clone()), Ok(()));
+
+        // after the ids wrap around the ones still in use and the default listener are skipped
+        NEXT_LISTENER.store(second, Ordering::Relaxed);
+        let third = create_listener(folder.clone()).unwrap();
+        assert_ne!(third, second);
+        NEXT_LISTENER.store(i32::MAX as u32, Ordering::Relaxed);
+        let (last, wrapped) = (create_listener(folder.clone()).unwrap(), create_listener(folder).unwrap());
+        assert!(last == i32::MAX as u32 && wrapped != DEFAULT_LISTENER);
+
+        for listener in [second, third, last, wrapped] {
+            assert_eq!(destroy_listener(listener), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let _globals = GLOBALS.lock().unwrap_or_else(|e| e.into_inner());
+        let unknown = GlrError::UnknownListener(DEFAULT_LISTENER);
+
+        shutdown_all();
+
+        // callbacks can be added before the default listener is started, which starts it
+        assert_eq!(destroy_listener(DEFAULT_LISTENER), Err(unknown.clone()));
+        assert_eq!(add_callback(DEFAULT_LISTENER, no_callback()), Ok(()));
+        assert_eq!(start_listener(std::env::temp_dir()), Ok(()));
+        let queue = create_queue(DEFAULT_LISTENER, SubscribeCode::Tokenizer, SubscriptionType::JSON, false, Delivery::default()).unwrap();
+
+        // shutting it down stops everything, the other functions don't start it again
+        shutdown_all();
+        assert_eq!(destroy_queue(queue), Err(GlrError::UnknownQueue(queue)));
+        assert_eq!(queue_dropped_events(queue), Err(GlrError::UnknownQueue(queue)));
+        assert_eq!(destroy_listener(DEFAULT_LISTENER), Err(unknown.clone()));
+        assert!(LISTENERS.get().unwrap().lock().unwrap().is_empty());
+
+        assert_eq!(remove_callback(DEFAULT_LISTENER, SubscribeCode::Tokenizer, 0), Ok(()));
+        assert_eq!(start_listener(std::env::temp_dir()), Ok(()));
+        assert_eq!(destroy_listener(DEFAULT_LISTENER), Ok(()));
+    }
+
+    #[test]
+    fn test_process_seed() {
+        let training: LevelDescriptor = "TRAINING".parse().unwrap();
+        assert_eq!(process_seed(training, 1, no_callback()), Err(GlrError::NoDescriptor("TRAINING".to_owned())));
+    }
 }
@@ -0,0 +1,177 @@
+use std::{
+    ffi::c_void,
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::dll_exports::structs::ThreadSafePtr;
+
+/// called after every file of a job and once more when it stops
+pub type ProgressCallback = extern "C" fn(context: *const c_void, job: u32, progress: *const JobProgress);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum JobState {
+    #[default]
+    Running = 0,
+    Done = 1,
+    Cancelled = 2,
+}
+
+/// how far a job is, laid out the same for the C api
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct JobProgress {
+    pub files_done: u32,
+    pub files_total: u32,
+    /// files that could not be read, also counted in `files_done`
+    pub files_failed: u32,
+    pub state: JobState,
+    /// bytes of the lines read so far, line endings count as one byte
+    pub bytes_processed: u64,
+}
+
+/// a `process_paths` running on its own thread
+pub struct Job {
+    id: u32,
+    files_total: u32,
+    files_done: AtomicU32,
+    files_failed: AtomicU32,
+    bytes_processed: AtomicU64,
+    cancelled: AtomicBool,
+
+    state: Mutex<JobState>,
+    stopped: Condvar,
+
+    progress_callback: Option<(ProgressCallback, ThreadSafePtr)>,
+}
+
+impl Job {
+    pub fn new(id: u32, files_total: u32, progress_callback: Option<(ProgressCallback, ThreadSafePtr)>) -> Job {
+        Job {
+            id,
+            files_total,
+            files_done: AtomicU32::new(0),
+            files_failed: AtomicU32::new(0),
+            bytes_processed: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+            state: Mutex::new(JobState::Running),
+            stopped: Condvar::new(),
+            progress_callback,
+        }
+    }
+
+    pub fn progress(&self) -> JobProgress {
+        JobProgress {
+            files_done: self.files_done.load(Ordering::Relaxed),
+            files_total: self.files_total,
+            files_failed: self.files_failed.load(Ordering::Relaxed),
+            state: self.state.lock().map(|state| *state).unwrap_or_default(),
+            bytes_processed: self.bytes_processed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// the job stops before the next line it would read
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn add_bytes(&self, bytes: usize) {
+        self.bytes_processed.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn file_done(&self, read: bool) {
+        if !read {
+            self.files_failed.fetch_add(1, Ordering::Relaxed);
+        }
+        self.files_done.fetch_add(1, Ordering::Relaxed);
+        self.report();
+    }
+
+    /// marks the job as done or cancelled and wakes up anyone waiting. A job
+    /// that read all its files is done even if it was cancelled afterwards.
+    pub fn finish(&self) {
+        let unfinished = self.files_done.load(Ordering::Relaxed) < self.files_total;
+        if let Ok(mut state) = self.state.lock() {
+            *state = match self.is_cancelled() && unfinished {
+                true => JobState::Cancelled,
+                false => JobState::Done,
+            };
+        }
+        self.stopped.notify_all();
+        self.report();
+    }
+
+    /// blocks until the job stopped or the timeout runs out. Returns whether it stopped.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let Ok(state) = self.state.lock() else {
+            return false;
+        };
+
+        self.stopped
+            .wait_timeout_while(state, timeout, |state| *state == JobState::Running)
+            .map(|(state, _)| *state != JobState::Running)
+            .unwrap_or(false)
+    }
+
+    fn report(&self) {
+        if let Some((callback, context)) = &self.progress_callback {
+            callback(context.get_ptr(), self.id, &self.progress());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, thread};
+
+    use super::*;
+    use crate::dll_exports::{
+        enums::{SubscribeCode, SubscriptionType},
+        functions,
+        structs::CallbackInfo,
+    };
+
+    #[test]
+    fn test_job() {
+        let _globals = functions::tests::GLOBALS.lock().unwrap_or_else(|e| e.into_inner());
+        let path = std::env::temp_dir().join(format!("glr_job_test_{}.txt", std::process::id()));
+        fs::write(&path, "first line\nsecond\n").unwrap();
+
+        let callback = CallbackInfo::new(SubscribeCode::Tokenizer, SubscriptionType::JSON, 0, std::ptr::null::<()>().into(), None);
+        let paths = vec![path.clone(), PathBuf::from("does/not/exist.txt")];
+
+        let job = functions::process_paths_async(paths, callback, None);
+        assert_eq!(functions::wait_job(job, Duration::from_secs(5)), Ok(true));
+        assert_eq!(
+            functions::job_progress(job),
+            Ok(JobProgress { files_done: 2, files_total: 2, files_failed: 1, state: JobState::Done, bytes_processed: 18 })
+        );
+        assert_eq!(functions::destroy_job(job), Ok(()));
+        assert!(functions::job_progress(job).is_err());
+        fs::remove_file(&path).unwrap();
+
+        // waiting on a cancelled job returns once its thread finishes
+        let job = Job::new(0, 1, None);
+        job.cancel();
+        thread::scope(|s| {
+            s.spawn(|| job.finish());
+            assert!(job.wait(Duration::from_secs(5)));
+        });
+        assert_eq!(job.progress().state, JobState::Cancelled);
+
+        // cancelling after the last file came too late to stop anything
+        let job = Job::new(0, 1, None);
+        job.file_done(true);
+        job.cancel();
+        job.finish();
+        assert_eq!(job.progress().state, JobState::Done);
+    }
+}
@@ -1,6 +1,14 @@
+pub mod bitdata;
+pub mod csv_record;
+pub mod dispatcher;
 pub mod enums;
+pub mod error;
+pub mod event_queue;
+pub mod filter;
 pub mod functions;
+pub mod jobs;
 pub mod structs;
+pub mod xml;
 
 pub mod callback_handler;
 pub mod token_parsers;
@@ -1,5 +1,5 @@
 use std::{
-    ffi::c_char, os::raw::c_void, path::PathBuf, sync::mpsc::{self, Receiver, Sender}, thread::{self, JoinHandle}, time::Duration
+    collections::HashMap, ffi::c_char, os::raw::c_void, path::PathBuf, sync::{Arc, mpsc::{self, Receiver, Sender}}, thread::{self, JoinHandle}, time::Duration
 };
 
 use might_sleep::prelude::CpuLimiter;
@@ -8,13 +8,45 @@ use crate::{
     core::{
         token_parser::{IterTokenParser, TokenParser}, tokenizer::{AllTokenizer, TokenizeIter, TokenizerGetIter}
     }, dll_exports::{
-        callback_handler::CallbackWrapper, enums::{SubscribeCode, SubscriptionType}, token_parsers::{
-            TokenParserInner, token_parser_base::TokenParserBase, token_parser_locations::TokenParserLocations, token_parser_runs::TokenParserRuns, token_parser_seeds::TokenParserSeed
+        callback_handler::CallbackWrapper, enums::{SubscribeCode, SubscriptionType}, dispatcher::{Delivery, Dispatcher}, event_queue::EventQueue, filter::EventFilter, jobs::Job, token_parsers::{
+            TokenParserInner, token_parser_base::TokenParserBase, token_parser_locations::TokenParserLocations, token_parser_runs::TokenParserRuns, token_parser_seeds::TokenParserSeed, token_parser_seed_trace::TokenParserSeedTrace
         }
     }, output_trait::OutputTrait, readers::{file_reader::FileReader, folder_watcher::FolderWatcher},
 };
 
 pub type EventCallback = extern "C" fn(context: *const c_void, message: *const c_char);
+pub type BinaryEventCallback = extern "C" fn(context: *const c_void, data: *const u8, len: usize);
+
+/// the function a message is given to, which one depends on the `SubscriptionType`
+#[derive(Debug, Clone)]
+pub enum CallbackFn {
+    /// nul terminated text formats (JSON)
+    Text(EventCallback),
+    /// binary formats (BITDATA), the data is only valid for the length given
+    Binary(BinaryEventCallback),
+    /// kept until the host polls it, see `create_queue`
+    Queue(Arc<EventQueue>),
+    /// given to a `Text` or `Binary` function on the dispatcher's thread
+    Dispatched(Arc<Dispatcher>),
+}
+
+impl CallbackFn {
+    /// casts the function pointer given through the C api to the signature
+    /// `message_type` is output with. Returns None for a null pointer.
+    ///
+    /// # Safety
+    /// `ptr` must be null or a function with that signature
+    pub unsafe fn from_ptr(message_type: SubscriptionType, ptr: *const c_void) -> Option<CallbackFn> {
+        if ptr.is_null() {
+            return None;
+        }
+
+        Some(match message_type.is_binary() {
+            true => CallbackFn::Binary(unsafe { std::mem::transmute::<*const c_void, BinaryEventCallback>(ptr) }),
+            false => CallbackFn::Text(unsafe { std::mem::transmute::<*const c_void, EventCallback>(ptr) }),
+        })
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct ThreadSafePtr {
@@ -45,7 +77,12 @@ pub struct CallbackInfo {
     channel_id: u32,
 
     context: ThreadSafePtr,
-    event_callback: Option<EventCallback>,
+    event_callback: Option<CallbackFn>,
+
+    envelope: bool,
+    sequence: u64,
+    filter: Option<EventFilter>,
+    delivery: Delivery,
 }
 
 impl CallbackInfo {
@@ -54,7 +91,7 @@ impl CallbackInfo {
         message_type: SubscriptionType,
         channel_id: u32,
         context: ThreadSafePtr,
-        event_callback: Option<EventCallback>,
+        event_callback: Option<CallbackFn>,
     ) -> CallbackInfo {
         CallbackInfo {
             code,
@@ -62,9 +99,63 @@ impl CallbackInfo {
             channel_id,
             context,
             event_callback,
+            envelope: false,
+            sequence: 0,
+            filter: None,
+            delivery: Delivery::default(),
+        }
+    }
+
+    /// wraps every message in an `Envelope`
+    pub fn with_envelope(mut self, envelope: bool) -> CallbackInfo {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn has_envelope(&self) -> bool {
+        self.envelope
+    }
+
+    /// only sends the events the filter accepts
+    pub fn with_filter(mut self, filter: Option<EventFilter>) -> CallbackInfo {
+        self.filter = filter;
+        self
+    }
+
+    /// the queue used when it is given to a live listener
+    pub fn with_delivery(mut self, delivery: Delivery) -> CallbackInfo {
+        self.delivery = delivery;
+        self
+    }
+
+    /// moves the function to a `Dispatcher` so the messages are sent from its
+    /// thread, returning the dispatcher. Queues are left as they are.
+    pub fn dispatched(mut self) -> (CallbackInfo, Option<Arc<Dispatcher>>) {
+        if !matches!(self.event_callback, Some(CallbackFn::Text(_) | CallbackFn::Binary(_))) {
+            return (self, None);
+        }
+
+        let inner = self.clone();
+        let dispatcher = Arc::new(Dispatcher::spawn(self.delivery, move |message| inner.send(message)));
+
+        self.event_callback = Some(CallbackFn::Dispatched(Arc::clone(&dispatcher)));
+        (self, Some(dispatcher))
+    }
+
+    /// whether an event with that variant name should be sent
+    pub fn accepts(&self, name: Option<&str>) -> bool {
+        match (&self.filter, name) {
+            (Some(filter), Some(name)) => filter.accepts(name),
+            _ => true,
         }
     }
 
+    /// the sequence number of the next enveloped message
+    pub fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence - 1
+    }
+
     pub fn get_code(&self) -> SubscribeCode {
         self.code
     }
@@ -77,18 +168,31 @@ impl CallbackInfo {
         self.channel_id
     }
 
-    pub fn get_event_callback(&self) -> &Option<EventCallback> {
+    pub fn get_event_callback(&self) -> &Option<CallbackFn> {
         &self.event_callback
     }
 
     pub fn get_context(&self) -> &ThreadSafePtr {
         &self.context
     }
+
+    /// gives a message made by `SubscriptionType::convert` to the callback
+    pub fn send(&self, message: &[u8]) {
+        match &self.event_callback {
+            Some(CallbackFn::Text(event)) => event(self.context.get_ptr(), message.as_ptr() as *const c_char),
+            Some(CallbackFn::Binary(event)) => event(self.context.get_ptr(), message.as_ptr(), message.len()),
+            Some(CallbackFn::Queue(queue)) => queue.push(message.to_vec()),
+            Some(CallbackFn::Dispatched(dispatcher)) => dispatcher.get_queue().push(message.to_vec()),
+            None => {}
+        }
+    }
 }
 
 pub struct MainThread {
     folder_watcher: FolderWatcher,
     send_callbacks: Sender<CallbackInfo>,
+    /// the dispatcher queues of the callbacks, to read their counters
+    dispatchers: HashMap<(SubscribeCode, u32), Arc<Dispatcher>>,
 
     shutdown: Sender<()>,
     join: Option<JoinHandle<()>>,
@@ -106,16 +210,39 @@ impl MainThread {
         MainThread {
             folder_watcher,
             send_callbacks: callback_sender,
+            dispatchers: HashMap::new(),
             shutdown: shutdown_sender,
             join: Some(join),
         }
     }
 
-    pub fn register_callback(&self, callback: CallbackInfo) {
+    /// the callback gets its own dispatcher, see `CallbackInfo::dispatched`.
+    /// A callback it replaces is not called anymore once this returns.
+    pub fn register_callback(&mut self, callback: CallbackInfo) {
+        let key = (callback.get_code(), callback.get_id());
+        let (callback, dispatcher) = callback.dispatched();
+
+        let replaced = match dispatcher {
+            Some(dispatcher) => self.dispatchers.insert(key, dispatcher),
+            None => self.dispatchers.remove(&key),
+        };
+        if let Some(replaced) = replaced {
+            replaced.stop();
+        }
         let _ = self.send_callbacks.send(callback);
     }
 
-    pub fn remove_callback(&self, code: SubscribeCode, id: u32) {
+    /// how many messages the callback's dispatcher dropped
+    pub fn dropped_events(&self, code: SubscribeCode, id: u32) -> Option<u64> {
+        self.dispatchers.get(&(code, id)).map(|dispatcher| dispatcher.get_queue().dropped())
+    }
+
+    /// the callback is not called anymore once this returns, the messages it
+    /// didn't get yet are dropped
+    pub fn remove_callback(&mut self, code: SubscribeCode, id: u32) {
+        if let Some(dispatcher) = self.dispatchers.remove(&(code, id)) {
+            dispatcher.stop();
+        }
         let _ = self.send_callbacks.send(CallbackInfo {
             channel_id: id,
             code,
@@ -123,7 +250,11 @@ impl MainThread {
                 p: 0 as *mut c_void,
             },
             event_callback: None,
-            message_type: 0.into(),
+            message_type: SubscriptionType::default(),
+            envelope: false,
+            sequence: 0,
+            filter: None,
+            delivery: Delivery::default(),
         });
     }
 
@@ -152,26 +283,49 @@ impl MainThread {
         }
     }
 
-    pub fn static_run<TP: TokenParserInner + Default>(mut paths: Vec<PathBuf>, callback: CallbackInfo) {
+    /// returns the paths that could not be read. If given a job it reports
+    /// the progress to it and stops when it is cancelled.
+    pub fn static_run<TP: TokenParserInner + Default>(
+        mut paths: Vec<PathBuf>,
+        callback: CallbackInfo,
+        job: Option<&Job>,
+    ) -> Vec<PathBuf> {
+        let mut failed = Vec::new();
         let mut parser = CallbackWrapper::<TP>::default();
+        let is_cancelled = || job.is_some_and(Job::is_cancelled);
 
         parser.add_callback(callback);
         
         while let Some(path) = paths.pop() {
+            if is_cancelled() {
+                break;
+            }
+
             parser.reset_token_parser();
+            parser.set_source(Some(path.to_string_lossy().into_owned()));
             
             let Some(text) = FileReader::static_read(path.clone()) else {
-                println!("Could not read path: {:?}", path);
+                failed.push(path);
+                job.inspect(|job| job.file_done(false));
                 continue;
             };
 
+            let text = text
+                .take_while(|_| !is_cancelled())
+                .inspect(|line| {
+                    job.inspect(|job| job.add_bytes(line.len() + 1));
+                });
+
             let tok_iter = TokenizeIter::new(
                 text, 
                 AllTokenizer
             );
 
             parser.parse_tokens(tok_iter);
+            job.inspect(|job| job.file_done(true));
         }
+
+        failed
     }
 
     fn thread_run(
@@ -186,6 +340,7 @@ impl MainThread {
         let mut parser_seeds = CallbackWrapper::<TokenParserSeed>::default();
         let mut parser_mapper = CallbackWrapper::<TokenParserLocations>::default();
         let mut parser_runs = CallbackWrapper::<TokenParserRuns>::default();
+        let mut parser_seed_trace = CallbackWrapper::<TokenParserSeedTrace>::default();
 
         loop {
             if let Ok(()) = shutdown.try_recv() {
@@ -199,6 +354,7 @@ impl MainThread {
                         SubscribeCode::RunInfo => parser_runs.add_callback(callback),
                         SubscribeCode::Mapper => parser_mapper.add_callback(callback),
                         SubscribeCode::SeedIndexer => parser_seeds.add_callback(callback),
+                        SubscribeCode::SeedTrace => parser_seed_trace.add_callback(callback),
                     }
                 } else {
                     match callback.code {
@@ -206,6 +362,7 @@ impl MainThread {
                         SubscribeCode::RunInfo => parser_runs.remove_callback(callback.get_id()),
                         SubscribeCode::Mapper => parser_mapper.remove_callback(callback.get_id()),
                         SubscribeCode::SeedIndexer => parser_seeds.remove_callback(callback.get_id()),
+                        SubscribeCode::SeedTrace => parser_seed_trace.remove_callback(callback.get_id()),
                     }
                 }
             }
@@ -215,6 +372,14 @@ impl MainThread {
                 parser_seeds.reset_token_parser();
                 parser_mapper.reset_token_parser();
                 parser_runs.reset_token_parser();
+                parser_seed_trace.reset_token_parser();
+
+                let source = file_reader.get_path().map(|p| p.to_string_lossy().into_owned());
+                parser_base.set_source(source.clone());
+                parser_seeds.set_source(source.clone());
+                parser_mapper.set_source(source.clone());
+                parser_runs.set_source(source.clone());
+                parser_seed_trace.set_source(source);
             }
 
             if let Some(new_lines) = file_reader.get_new_lines() {
@@ -225,6 +390,10 @@ impl MainThread {
                         parser_mapper.parse_token(time, &token);
                         parser_seeds.parse_token(time, &token);
                         parser_runs.parse_token(time, &token);
+                        // tracing generates the whole level a second time so only do it when asked
+                        if parser_seed_trace.has_callbacks() {
+                            parser_seed_trace.parse_token(time, &token);
+                        }
                     });
             }
 
@@ -235,6 +404,8 @@ impl MainThread {
 
 impl Drop for MainThread {
     fn drop(&mut self) {
+        // a blocking dispatcher queue would keep the thread from seeing the shutdown
+        self.dispatchers.values().for_each(|dispatcher| dispatcher.get_queue().close());
         let _ = self.shutdown.send(());
         let _ = match self.join.take() {
             Some(jh) => {
@@ -1,16 +1,17 @@
 use glr_core::{time::Time, token::Token};
 use serde::Serialize;
 
-use crate::output_trait::OutputTrait;
+use crate::{dll_exports::csv_record::CsvRecord, output_trait::OutputTrait};
 
 
 pub mod token_parser_base;
 pub mod token_parser_locations;
 pub mod token_parser_runs;
 pub mod token_parser_seeds;
+pub mod token_parser_seed_trace;
 
 pub trait TokenParserInner {
-    type Output: Serialize;
+    type Output: Serialize + CsvRecord;
 
     fn parse(&mut self, time: Time, token: &Token, callback_handler: &mut impl OutputTrait<Self::Output>);
     fn parse_tokens(
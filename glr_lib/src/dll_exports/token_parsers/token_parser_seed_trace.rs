@@ -0,0 +1,35 @@
+use glr_core::{seed_indexer_result::{OutputSeedIndexer, OutputSeedTrace, SeedStream}, time::Time, token::Token};
+
+use crate::{
+    dll_exports::token_parsers::TokenParserInner, output_trait::OutputTrait, seed_gen::{consumers::base_consumer::Consumer, levels::LevelDescriptors, seed_trace::{self, TracedIter}, unity_random::UnityRandom}
+};
+
+#[derive(Default)]
+pub struct TokenParserSeedTrace {
+    level_descriptors: LevelDescriptors,
+}
+
+impl TokenParserInner for TokenParserSeedTrace {
+    type Output = OutputSeedTrace;
+
+    fn parse(&mut self, _: Time, token: &Token, callback_handler: &mut impl OutputTrait<OutputSeedTrace>) {
+        if let Token::SelectExpedition(level, seed) = token {
+            self.level_descriptors.refresh_overrides();
+
+            callback_handler.output(OutputSeedTrace::GenerationStart(level.to_string()));
+
+            if let Some(level_data) = self.level_descriptors.get_level(level) {
+                let mut seed_iter = TracedIter::new(UnityRandom::from(*seed), SeedStream::Level);
+                let (_, entries) = seed_trace::record(|| {
+                    level_data.take(&mut seed_iter, &mut Vec::<OutputSeedIndexer>::new())
+                });
+
+                for entry in entries {
+                    callback_handler.output(OutputSeedTrace::Value(entry));
+                }
+            }
+
+            callback_handler.output(OutputSeedTrace::GenerationEnd);
+        }
+    }
+}
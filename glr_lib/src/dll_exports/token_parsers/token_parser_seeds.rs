@@ -2,7 +2,7 @@
 use glr_core::{seed_indexer_result::OutputSeedIndexer, time::Time, token::Token};
 
 use crate::{
-    dll_exports::token_parsers::TokenParserInner, output_trait::OutputTrait, seed_gen::{consumers::base_consumer::Consumer, levels::LevelDescriptors, unity_random::UnityRandom}
+    dll_exports::token_parsers::TokenParserInner, output_trait::OutputTrait, seed_gen::{consumers::base_consumer::Consumer, layout_variants::KnownLayoutOutput, levels::LevelDescriptors, unity_random::UnityRandom}
 };
 
 #[derive(Default)]
@@ -15,12 +15,15 @@ impl TokenParserInner for TokenParserSeed {
 
     fn parse(&mut self, _: Time, token: &Token, callback_handler: &mut impl OutputTrait<OutputSeedIndexer>) {
         if let Token::SelectExpedition(level, seed) = token {
-            callback_handler.output(OutputSeedIndexer::GenerationStart(level.to_string()));
+            self.level_descriptors.refresh_overrides();
+
+            let level_name = level.to_string();
+            callback_handler.output(OutputSeedIndexer::GenerationStart(level_name.clone()));
 
             let mut unity_random = UnityRandom::from(*seed);
             self.level_descriptors
                 .get_level(&level)
-                .map(|v| v.take(&mut unity_random, callback_handler));
+                .map(|v| v.take(&mut unity_random, &mut KnownLayoutOutput::new(&level_name, callback_handler)));
 
             callback_handler.output(OutputSeedIndexer::GenerationEnd);
         }
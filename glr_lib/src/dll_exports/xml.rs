@@ -0,0 +1,141 @@
+//! the XML (`SubscriptionType::XML`) message layout.
+//!
+//! Messages are the JSON output written in the XPath 3.1 `json-to-xml` format
+//! (https://www.w3.org/TR/xpath-functions-31/#json-to-xml-mapping) so they carry
+//! exactly the same data and can be turned back into the JSON with `xml-to-json`.
+//! Objects are `<map>`, arrays `<array>`, and values `<string>`, `<number>`,
+//! `<boolean>` or `<null/>`, with the field name in the `key` attribute when
+//! inside a map. Characters XML can't contain are replaced with U+FFFD.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+use serde_json::Value;
+
+pub const XML_NAMESPACE: &str = "http://www.w3.org/2005/xpath-functions";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => escaped.push('\u{FFFD}'),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn write_value(out: &mut String, value: &Value, key: Option<&str>, namespace: bool) {
+    let mut attributes = String::new();
+    if namespace {
+        let _ = write!(attributes, " xmlns=\"{XML_NAMESPACE}\"");
+    }
+    if let Some(key) = key {
+        let _ = write!(attributes, " key=\"{}\"", escape(key));
+    }
+
+    let _ = match value {
+        Value::Null => write!(out, "<null{attributes}/>"),
+        Value::Bool(b) => write!(out, "<boolean{attributes}>{b}</boolean>"),
+        Value::Number(n) => write!(out, "<number{attributes}>{n}</number>"),
+        Value::String(s) => write!(out, "<string{attributes}>{}</string>", escape(s)),
+        Value::Array(values) => {
+            let _ = write!(out, "<array{attributes}>");
+            for value in values {
+                write_value(out, value, None, false);
+            }
+            write!(out, "</array>")
+        }
+        Value::Object(map) => {
+            let _ = write!(out, "<map{attributes}>");
+            for (key, value) in map {
+                write_value(out, value, Some(key), false);
+            }
+            write!(out, "</map>")
+        }
+    };
+}
+
+/// the JSON output of `data` as XML. Map entries are ordered by key.
+pub fn to_xml<O: Serialize>(data: &O) -> Option<String> {
+    // going through the json text keeps floats the same as in the JSON output
+    let value: Value = serde_json::from_str(&serde_json::to_string(data).ok()?).ok()?;
+
+    let mut out = String::new();
+    write_value(&mut out, &value, None, true);
+    Some(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use quick_xml::{Reader, events::Event};
+    use serde_json::{Map, Number, Value};
+
+    /// reads a message back into the json value, the reverse of `to_xml`
+    pub fn from_xml(xml: &str) -> Value {
+        let mut reader = Reader::from_str(xml);
+        // the containers being read with the key they will be inserted with
+        let mut stack: Vec<(Option<String>, Value)> = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Empty(e) => {
+                    if stack.is_empty() {
+                        return Value::Null;
+                    }
+                    let key = e.try_get_attribute("key").unwrap().map(|a| a.unescape_value().unwrap().into_owned());
+                    insert(&mut stack, key, Value::Null);
+                }
+                Event::Start(e) => {
+                    let key = e.try_get_attribute("key").unwrap().map(|a| a.unescape_value().unwrap().into_owned());
+                    let value = match e.name().as_ref() {
+                        b"map" => Value::Object(Map::new()),
+                        b"array" => Value::Array(Vec::new()),
+                        _ => Value::Null,
+                    };
+                    stack.push((key, value));
+                    text.clear();
+                }
+                Event::Text(e) => text.push_str(&e.decode().unwrap()),
+                Event::GeneralRef(e) => {
+                    let name = e.decode().unwrap();
+                    text.push_str(quick_xml::escape::unescape(&format!("&{name};")).unwrap().as_ref());
+                }
+                Event::End(e) => {
+                    let (key, value) = stack.pop().unwrap();
+                    let value = match e.name().as_ref() {
+                        b"string" => Value::String(std::mem::take(&mut text)),
+                        b"number" => Value::Number(std::mem::take(&mut text).parse::<Number>().unwrap()),
+                        b"boolean" => Value::Bool(std::mem::take(&mut text) == "true"),
+                        _ => value,
+                    };
+
+                    if stack.is_empty() {
+                        return value;
+                    }
+                    insert(&mut stack, key, value);
+                }
+                Event::Eof => panic!("message ended before the root element was closed"),
+                _ => {}
+            }
+        }
+    }
+
+    fn insert(stack: &mut [(Option<String>, Value)], key: Option<String>, value: Value) {
+        match stack.last_mut() {
+            Some((_, Value::Object(map))) => {
+                map.insert(key.unwrap(), value);
+            }
+            Some((_, Value::Array(values))) => values.push(value),
+            _ => panic!("value outside of a map or array"),
+        }
+    }
+}
@@ -1,12 +1,12 @@
-#![no_main]
+#![cfg_attr(not(test), no_main)]
 
 mod core;
 mod readers;
 
 mod mapper;
 mod run_gen;
-mod seed_gen;
+pub mod seed_gen;
 
 pub mod dll_exports;
 mod load_seed_consumers;
-mod output_trait;
+pub mod output_trait;
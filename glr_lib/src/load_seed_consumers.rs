@@ -1,8 +1,17 @@
-use std::{collections::BTreeMap, sync::LazyLock};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, RwLock},
+    time::SystemTime,
+};
 #[cfg(debug_assertions)]
 use std::{fs::File, io::Read};
 
-use crate::seed_gen::zone_info::level_data::LevelData;
+use crate::seed_gen::{
+    descriptor_validator::{Severity, validate_descriptors},
+    zone_info::level_data::LevelData,
+};
 
 pub struct LoadSeedConsumers;
 
@@ -11,6 +20,27 @@ pub static SEED_LEVEL_DATAS: LazyLock<Option<BTreeMap<String, LevelData>>> =
         LoadSeedConsumers::load_all()
     });
 
+/// a file of levels loaded at runtime that replace the ones included in the build
+struct OverrideSource {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    levels: BTreeMap<String, Arc<LevelData>>,
+}
+
+/// the files in load order, a level is taken from the last file that has it.
+/// `reload_error` is the last time a changed file could not be loaded again.
+#[derive(Default)]
+struct LevelOverrides {
+    sources: Vec<OverrideSource>,
+    reload_error: Option<String>,
+}
+
+static LEVEL_OVERRIDES: LazyLock<RwLock<LevelOverrides>> = LazyLock::new(Default::default);
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
 impl LoadSeedConsumers {
     #[cfg(not(debug_assertions))]
     pub fn load_all() -> Option<BTreeMap<String, LevelData>> {
@@ -48,4 +78,189 @@ impl LoadSeedConsumers {
             }
         }
     }
+
+    fn read_overrides(path: &Path) -> Result<BTreeMap<String, LevelData>, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+        let errors: Vec<String> = validate_descriptors(&json)
+            .map_err(|e| format!("{} is not a valid descriptors file: {e}", path.display()))?
+            .into_iter()
+            .filter(|v| v.severity == Severity::Error)
+            .map(|v| v.to_string())
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+
+        serde_json::from_str(&json)
+            .map_err(|e| format!("{} is not a valid descriptors file: {e}", path.display()))
+    }
+
+    fn read_source(path: &Path) -> Result<OverrideSource, String> {
+        let modified = modified_time(path);
+        let levels = Self::read_overrides(path)?;
+
+        Ok(OverrideSource {
+            path: path.to_path_buf(),
+            modified,
+            levels: levels.into_iter().map(|(k, v)| (k, Arc::new(v))).collect(),
+        })
+    }
+
+    /// loads the levels in the json at `path` on top of the included ones and the
+    /// other files, replacing any level with the same name. Loading a file again
+    /// replaces the levels it had. Returns how many levels were loaded.
+    pub fn load_overrides(path: impl AsRef<Path>) -> Result<usize, String> {
+        let source = Self::read_source(path.as_ref())?;
+        let count = source.levels.len();
+
+        let mut overrides = LEVEL_OVERRIDES.write().map_err(|e| e.to_string())?;
+        overrides.sources.retain(|v| v.path != source.path);
+        overrides.sources.push(source);
+        overrides.reload_error = None;
+
+        Ok(count)
+    }
+
+    pub fn clear_overrides() {
+        if let Ok(mut overrides) = LEVEL_OVERRIDES.write() {
+            *overrides = LevelOverrides::default();
+        }
+    }
+
+    /// reads the override files that changed since they were loaded again. A file
+    /// that fails keeps its previous levels and the error is kept for `reload_error`
+    /// until a reload works. Returns whether anything was reloaded.
+    pub fn reload_overrides() -> Result<bool, String> {
+        let changed: Vec<PathBuf> = LEVEL_OVERRIDES
+            .read()
+            .map_err(|e| e.to_string())?
+            .sources
+            .iter()
+            .filter(|v| modified_time(&v.path) != v.modified)
+            .map(|v| v.path.clone())
+            .collect();
+        if changed.is_empty() {
+            return Ok(false);
+        }
+
+        let mut reloaded = Vec::with_capacity(changed.len());
+        let mut errors = Vec::new();
+        for path in &changed {
+            match Self::read_source(path) {
+                Ok(source) => reloaded.push(source),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let mut overrides = LEVEL_OVERRIDES.write().map_err(|e| e.to_string())?;
+        for source in reloaded {
+            // a file cleared in the meantime stays cleared
+            if let Some(old) = overrides.sources.iter_mut().find(|v| v.path == source.path) {
+                *old = source;
+            }
+        }
+        overrides.reload_error = (!errors.is_empty()).then(|| errors.join("\n"));
+
+        match &overrides.reload_error {
+            Some(e) => Err(e.clone()),
+            None => Ok(true),
+        }
+    }
+
+    /// why the last reload of the override files failed, if it did
+    pub fn reload_error() -> Option<String> {
+        LEVEL_OVERRIDES.read().ok()?.reload_error.clone()
+    }
+
+    pub fn get_override(level_name: &str) -> Option<Arc<LevelData>> {
+        LEVEL_OVERRIDES
+            .read()
+            .ok()?
+            .sources
+            .iter()
+            .rev()
+            .find_map(|v| v.levels.get(level_name))
+            .cloned()
+    }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, time::Duration};
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::seed_gen::LEVEL_DESCRIPTORS_JSON;
+
+    /// writes a file with R1A1 as each of `levels` with its build seed and moves its modified time to `age`
+    fn write_levels(path: &Path, levels: &[(&str, i64)], age: u64) {
+        let mut included: BTreeMap<String, Value> =
+            serde_json::from_str(LEVEL_DESCRIPTORS_JSON).unwrap();
+        let r1a1 = included.remove("R1A1").unwrap();
+
+        let levels: BTreeMap<&str, Value> = levels
+            .iter()
+            .map(|(name, build_seed)| {
+                let mut level = r1a1.clone();
+                level["build_seed"] = (*build_seed).into();
+                (*name, level)
+            })
+            .collect();
+        fs::write(path, serde_json::to_string(&levels).unwrap()).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(age))
+            .unwrap();
+    }
+
+    fn build_seed(level: &str) -> Option<i32> {
+        LoadSeedConsumers::get_override(level).map(|v| v.build_seed)
+    }
+
+    #[test]
+    fn test_overrides() {
+        let dir = std::env::temp_dir();
+        let first = dir.join(format!("glr_overrides_first_{}.json", std::process::id()));
+        let second = dir.join(format!("glr_overrides_second_{}.json", std::process::id()));
+
+        // the last file loaded wins
+        write_levels(&first, &[("TEST_A", 1), ("TEST_B", 1)], 100);
+        write_levels(&second, &[("TEST_B", 2)], 100);
+        assert_eq!(LoadSeedConsumers::load_overrides(&first), Ok(2));
+        assert_eq!(LoadSeedConsumers::load_overrides(&second), Ok(1));
+        assert_eq!((build_seed("TEST_A"), build_seed("TEST_B")), (Some(1), Some(2)));
+
+        // loading a file again replaces what it had instead of adding to it
+        write_levels(&first, &[("TEST_B", 3)], 100);
+        assert_eq!(LoadSeedConsumers::load_overrides(&first), Ok(1));
+        assert_eq!((build_seed("TEST_A"), build_seed("TEST_B")), (None, Some(3)));
+
+        // only files with a new modified time are read again, and a level removed from one falls back to the others
+        write_levels(&first, &[("TEST_A", 4)], 100);
+        assert_eq!(LoadSeedConsumers::reload_overrides(), Ok(false));
+        assert_eq!((build_seed("TEST_A"), build_seed("TEST_B")), (None, Some(3)));
+        write_levels(&first, &[("TEST_A", 4)], 200);
+        assert_eq!(LoadSeedConsumers::reload_overrides(), Ok(true));
+        assert_eq!((build_seed("TEST_A"), build_seed("TEST_B")), (Some(4), Some(2)));
+
+        // a broken file keeps its levels and the error until it is fixed
+        fs::write(&first, "{").unwrap();
+        assert!(LoadSeedConsumers::reload_overrides().is_err());
+        assert!(LoadSeedConsumers::reload_error().is_some());
+        assert_eq!(build_seed("TEST_A"), Some(4));
+        write_levels(&first, &[("TEST_A", 5)], 300);
+        assert_eq!(LoadSeedConsumers::reload_overrides(), Ok(true));
+        assert_eq!((LoadSeedConsumers::reload_error(), build_seed("TEST_A")), (None, Some(5)));
+
+        LoadSeedConsumers::clear_overrides();
+        assert_eq!(build_seed("TEST_B"), None);
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+    }
+}
+
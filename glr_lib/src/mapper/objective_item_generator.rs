@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use glr_core::{
     location::{ItemIdentifier, Location},
     token::Token,
@@ -9,8 +11,8 @@ pub struct ObjectiveItemGenerator {
     collectable_mapper: Option<CollectableMapper>,
 
     dimension: usize,
-    buffer_names: Vec<ItemIdentifier>,
-    buffer_zones: Vec<(usize, u64)>,
+    buffer_names: VecDeque<ItemIdentifier>,
+    buffer_zones: VecDeque<(usize, u64)>,
 
     level_name: String,
     players: Vec<String>,
@@ -55,42 +57,39 @@ impl LocationGenerator for ObjectiveItemGenerator {
                 None
             }
             Token::CollectableAllocated(zone) => {
-                self.buffer_zones.push((self.dimension, *zone));
+                self.buffer_zones.push_back((self.dimension, *zone));
 
                 None
             }
             // found an item that does not have a seed
             Token::ObjectiveSpawnedOverride(id, name) => {
-                // unwrap should never fail since we always know we have collectable allocated
-                let (_, zone) = self.buffer_zones.pop().unwrap_or((9999, 9999));
-
-                Some(Location::BigObjective(
-                    Into::<&str>::into(name).to_owned(),
-                    zone,
-                    *id,
-                ))
+                // should always be there since we always know we have collectable allocated
+                let (_, zone) = self.buffer_zones.pop_back().unwrap_or((9999, 9999));
+
+                Some(name.into_location(zone, *id))
             }
             Token::CollectableItemID(id) => {
                 let repr = ItemIdentifier::from_repr(*id).unwrap_or(ItemIdentifier::Unknown(*id));
 
-                match repr {
-                    ItemIdentifier::Cryo | ItemIdentifier::Cargo => {
-                        // should never fail since we have collectable zone allocated
-                        let (_, zone) = self.buffer_zones.remove(0);
+                match repr.is_big_pickup() {
+                    true => {
+                        // should always be there since we have collectable zone allocated
+                        let (_, zone) = self.buffer_zones.pop_front()?;
 
                         Some(Location::BigCollectable(repr, zone))
                     }
-                    _ => {
-                        self.buffer_names.push(repr);
+                    false => {
+                        self.buffer_names.push_back(repr);
 
                         None
                     }
                 }
             }
             Token::CollectableItemSeed(seed) => {
-                let id = self.buffer_names.remove(0);
+                // a seed without its item id (e.g. the log started mid generation) is skipped
+                let id = self.buffer_names.pop_front()?;
                 if id != ItemIdentifier::DataCube && id != ItemIdentifier::DataCubeR8 {
-                    self.buffer_zones.sort_by(|(d1, z1), (d2, z2)| {
+                    self.buffer_zones.make_contiguous().sort_by(|(d1, z1), (d2, z2)| {
                         let c = d1.cmp(d2);
                         match c {
                             std::cmp::Ordering::Equal => z1.cmp(z2),
@@ -98,7 +97,7 @@ impl LocationGenerator for ObjectiveItemGenerator {
                         }
                     });
                 }
-                let (_, zone) = self.buffer_zones.remove(0);
+                let (_, zone) = self.buffer_zones.pop_front()?;
 
                 let new_seed = self
                     .collectable_mapper
@@ -134,3 +133,41 @@ impl LocationGenerator for ObjectiveItemGenerator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glr_core::data::{LevelDescriptor, ObjectiveFunction};
+
+    use super::*;
+
+    #[test]
+    fn test_objective_locations() {
+        let mut generator = ObjectiveItemGenerator::default();
+        generator.accept_token(&Token::SelectExpedition(LevelDescriptor::default(), 0));
+        generator.accept_token(&Token::GeneratingLevel);
+
+        let mut place = |zone, id, function| {
+            generator.accept_token(&Token::CollectableAllocated(zone));
+            generator.accept_token(&Token::ObjectiveSpawnedOverride(id, function))
+        };
+        assert!(matches!(
+            place(3, 12, ObjectiveFunction::TerminalUplink),
+            Some(Location::Terminal(ObjectiveFunction::TerminalUplink, 3, 12))
+        ));
+        assert!(matches!(
+            place(5, 4, ObjectiveFunction::Reactor_Shutdown),
+            Some(Location::Reactor(ObjectiveFunction::Reactor_Shutdown, 5, 4))
+        ));
+        assert!(matches!(place(1, 7, ObjectiveFunction::PowerCellDistribution), Some(Location::Generator(1, 7))));
+        assert!(matches!(
+            place(2, 2, ObjectiveFunction::CentralGeneratorCluster),
+            Some(Location::GeneratorCluster(2, 2))
+        ));
+
+        // a cell (131) waits for its seed, which has no zone left to go with, and a cryo (148) has none either
+        assert!(generator.accept_token(&Token::CollectableItemID(131)).is_none());
+        assert!(generator.accept_token(&Token::CollectableItemSeed(5)).is_none());
+        assert!(generator.accept_token(&Token::CollectableItemSeed(6)).is_none());
+        assert!(generator.accept_token(&Token::CollectableItemID(148)).is_none());
+    }
+}
@@ -9,6 +9,7 @@ pub struct FileReader {
     receiver: Receiver<PathBuf>,
 
     file: Option<BufReader<File>>,
+    path: Option<PathBuf>,
     last_position: u64,
 }
 
@@ -17,16 +18,23 @@ impl FileReader {
         Self {
             receiver,
             file: None,
+            path: None,
             last_position: 0,
         }
     }
 
     fn new_file(&mut self, path: PathBuf) {
         self.file.take();
-        self.file = File::open(path).map(|f| BufReader::new(f)).ok();
+        self.file = File::open(&path).map(|f| BufReader::new(f)).ok();
+        self.path = Some(path);
         self.last_position = 0;
     }
 
+    /// the log being read live
+    pub fn get_path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
     pub fn get_was_new_file(&mut self) -> bool {
         if let Ok(new_path) = self.receiver.try_recv() {
             println!("Reading live {:?}", new_path);
@@ -0,0 +1,377 @@
+//! finds `skip_start` and `build_seed_gate_count` of a level by comparing the
+//! seed indexer with the item locations the mapper found in real logs.
+
+use std::{
+    ops::RangeInclusive,
+    path::PathBuf,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use glr_core::{
+    data::LevelDescriptor,
+    location::{ItemIdentifier, Location},
+    seed_indexer_result::{ItemKind, OutputSeedIndexer},
+    token::Token,
+};
+
+use crate::{
+    dll_exports::{functions::process_paths_collect, token_parsers::token_parser_base::TokenParserBase},
+    mapper::{
+        key_generator::KeyGenerator, location_generator::LocationGenerator,
+        objective_item_generator::ObjectiveItemGenerator,
+    },
+    seed_gen::{consumers::base_consumer::Consumer, unity_random::UnityRandom, zone_info::level_data::LevelData},
+};
+
+/// the locations found in the logs for one generation of a level
+#[derive(Clone)]
+pub struct ObservedRun {
+    pub level: LevelDescriptor,
+    pub seed: i32,
+    pub locations: Vec<Location>,
+}
+
+impl ObservedRun {
+    /// reads every log on its own so the mapper state does not carry over between logs
+    pub fn from_logs(paths: &[PathBuf]) -> Vec<ObservedRun> {
+        paths
+            .iter()
+            .flat_map(|path| Self::from_tokens(process_paths_collect::<TokenParserBase>(vec![path.clone()])))
+            .collect()
+    }
+
+    /// splits the tokens into one run per level generation, runs without
+    /// any location the seed indexer can be compared with are dropped
+    pub fn from_tokens(tokens: impl IntoIterator<Item = Token>) -> Vec<ObservedRun> {
+        let mut key_gen = KeyGenerator::default();
+        let mut obj_gen = ObjectiveItemGenerator::default();
+        let mut expedition = None;
+        let mut runs: Vec<ObservedRun> = Vec::new();
+
+        for token in tokens {
+            if let Token::SelectExpedition(level, seed) = &token {
+                expedition = Some((level.clone(), *seed));
+            }
+
+            for location in [key_gen.accept_token(&token), obj_gen.accept_token(&token)].into_iter().flatten() {
+                match (location, &expedition) {
+                    (Location::GenerationStarted(_), Some((level, seed))) => runs.push(ObservedRun {
+                        level: level.clone(),
+                        seed: *seed,
+                        locations: Vec::new(),
+                    }),
+                    (Location::GenerationStarted(_), None) => {}
+                    (location, _) => {
+                        if let Some(run) = runs.last_mut().filter(|_| is_comparable(&location)) {
+                            run.locations.push(location);
+                        }
+                    }
+                }
+            }
+        }
+
+        runs.retain(|run| !run.locations.is_empty());
+        runs
+    }
+}
+
+/// terminals, reactors and generators are only known by their area so they can't be compared
+fn is_comparable(location: &Location) -> bool {
+    matches!(
+        location,
+        Location::ColoredKey(..) | Location::BulkheadKey(..) | Location::Gatherable(..) | Location::BigCollectable(..)
+    )
+}
+
+/// whether the seed indexer names the item `identifier` as `kind`. Objectives
+/// gathering items (`GatherSmallItems`, `RetrieveBigItems`) don't say which
+/// item it is so they are taken as any item, pickups are compared by name.
+fn is_item(kind: &ItemKind, identifier: &ItemIdentifier) -> bool {
+    let ItemKind::Objective(name) = kind else {
+        return false;
+    };
+
+    match name.as_str() {
+        "GatherSmallItems" | "RetrieveBigItems" => true,
+        "DATA_SPHERE" => *identifier == ItemIdentifier::Datasphere,
+        "FOG_TURBINE" => *identifier == ItemIdentifier::FogTurbine,
+        "MATTER_WAVE_PROJECTOR" => *identifier == ItemIdentifier::MWP,
+        _ => false,
+    }
+}
+
+/// whether the seed indexer placed an item where the log found it
+pub fn is_match(location: &Location, item: &OutputSeedIndexer) -> bool {
+    let OutputSeedIndexer::Key(kind, _, zone, id, _) = item else {
+        return false;
+    };
+    let at = |z: u64, i: Option<u64>| *zone as i64 == z as i64 && i.is_none_or(|i| *id as i64 == i as i64);
+
+    match location {
+        Location::ColoredKey(_, z, i) => matches!(kind, ItemKind::ColoredKey { .. }) && at(*z, Some(*i)),
+        Location::BulkheadKey(_, z, i) => matches!(kind, ItemKind::BulkheadKey { .. }) && at(*z, Some(*i)),
+        Location::Gatherable(identifier, z, i) => is_item(kind, identifier) && at(*z, Some(*i)),
+        Location::BigCollectable(ItemIdentifier::Cell, z) => matches!(kind, ItemKind::Cell) && at(*z, None),
+        Location::BigCollectable(identifier, z) => is_item(kind, identifier) && at(*z, None),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationCandidate {
+    pub skip_start: usize,
+    pub build_seed_gate_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalibrationResult {
+    /// comparable locations in all runs
+    pub total: usize,
+    /// how many of them the best candidates reproduce
+    pub matched: usize,
+    /// every candidate that reached `matched`
+    pub best: Vec<CalibrationCandidate>,
+}
+
+impl CalibrationResult {
+    pub fn is_exact(&self) -> bool {
+        self.total > 0 && self.matched == self.total
+    }
+}
+
+/// the locations of `runs` that `level` doesn't reproduce with the given values
+pub fn unmatched(level: &LevelData, runs: &[ObservedRun], candidate: CalibrationCandidate) -> Vec<(i32, Location)> {
+    let mut level_data = level.clone();
+    level_data.skip_start = candidate.skip_start;
+    level_data.build_seed_gate_count = candidate.build_seed_gate_count;
+
+    runs.iter()
+        .flat_map(|run| {
+            let mut layout = Vec::new();
+            level_data.take(&mut UnityRandom::from(run.seed), &mut layout);
+
+            run.locations
+                .iter()
+                .filter(|location| !layout.iter().any(|item| is_match(location, item)))
+                .map(|location| (run.seed, location.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// tries every combination of the two ranges on multiple threads and returns the
+/// ones that reproduce the most locations. `progress` is called with
+/// (candidates done, total candidates) after each one.
+pub fn calibrate<P>(
+    level: &LevelData,
+    runs: &[ObservedRun],
+    skip_start: RangeInclusive<usize>,
+    gate_count: RangeInclusive<usize>,
+    threads: usize,
+    progress: P,
+) -> CalibrationResult
+where
+    P: Fn(u64, u64) + Sync,
+{
+    let total = runs.iter().map(|run| run.locations.len()).sum();
+    let skips: Vec<usize> = skip_start.collect();
+    let gates: Vec<usize> = gate_count.collect();
+    let candidates = (skips.len() * gates.len()) as u64;
+
+    let next = AtomicUsize::new(0);
+    let done = AtomicU64::new(0);
+    let best = Mutex::new((0usize, Vec::new()));
+
+    thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| {
+                let mut level_data = level.clone();
+                let mut local: (usize, Vec<CalibrationCandidate>) = (0, Vec::new());
+
+                loop {
+                    let gate_id = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(gate) = gates.get(gate_id) else {
+                        break;
+                    };
+                    level_data.build_seed_gate_count = *gate;
+
+                    for skip in &skips {
+                        level_data.skip_start = *skip;
+
+                        let matched = runs
+                            .iter()
+                            .map(|run| {
+                                let mut layout = Vec::new();
+                                level_data.take(&mut UnityRandom::from(run.seed), &mut layout);
+
+                                run.locations
+                                    .iter()
+                                    .filter(|location| layout.iter().any(|item| is_match(location, item)))
+                                    .count()
+                            })
+                            .sum::<usize>();
+
+                        let candidate = CalibrationCandidate {
+                            skip_start: *skip,
+                            build_seed_gate_count: *gate,
+                        };
+                        if matched > local.0 || local.1.is_empty() {
+                            local = (matched, vec![candidate]);
+                        } else if matched == local.0 {
+                            local.1.push(candidate);
+                        }
+                    }
+
+                    let count = skips.len() as u64;
+                    progress(done.fetch_add(count, Ordering::Relaxed) + count, candidates);
+                }
+
+                if let Ok(mut best) = best.lock() {
+                    if local.0 > best.0 || best.1.is_empty() {
+                        *best = local;
+                    } else if local.0 == best.0 {
+                        best.1.extend(local.1);
+                    }
+                }
+            });
+        }
+    });
+
+    let (matched, mut best) = best.into_inner().unwrap_or_default();
+    best.sort_by_key(|c| (c.build_seed_gate_count, c.skip_start));
+
+    CalibrationResult { total, matched, best }
+}
+
+#[cfg(test)]
+mod tests {
+    use glr_core::data::{KeyDescriptor, ObjectiveFunction};
+
+    use super::*;
+    use crate::seed_gen::test_levels;
+
+    /// the colored keys the seed indexer places in R1A1 for each seed, as if they were read from logs
+    fn observed_keys(level: &LevelData) -> Vec<ObservedRun> {
+        [1, 2, 3]
+            .into_iter()
+            .map(|seed| {
+                let mut layout = Vec::new();
+                level.take(&mut UnityRandom::from(seed), &mut layout);
+
+                let locations = layout
+                    .iter()
+                    .filter_map(|v| match v {
+                        OutputSeedIndexer::Key(ItemKind::ColoredKey { for_zone }, _, zone, id, _) => Some(
+                            Location::ColoredKey(format!("KeyZ{for_zone}"), *zone as u64, *id as u64),
+                        ),
+                        _ => None,
+                    })
+                    .collect();
+
+                ObservedRun {
+                    level: "R1A1".parse().unwrap(),
+                    seed,
+                    locations,
+                }
+            })
+            .collect()
+    }
+
+    fn expected(level: &LevelData) -> CalibrationCandidate {
+        CalibrationCandidate {
+            skip_start: level.skip_start,
+            build_seed_gate_count: level.build_seed_gate_count,
+        }
+    }
+
+    #[test]
+    fn test_from_tokens() {
+        let key = |name: &str| Token::ItemAllocated(KeyDescriptor::try_from(name).unwrap());
+        let tokens = [
+            // nothing is kept before the level starts generating
+            key("KEY_BLUE_1"),
+            Token::ItemSpawn(50, 1),
+            Token::SelectExpedition("R1A1".parse().unwrap(), 5),
+            Token::GeneratingLevel,
+            key("KEY_BLUE_2"),
+            Token::ItemSpawn(51, 3),
+            Token::CollectableAllocated(52),
+            Token::ObjectiveSpawnedOverride(7, ObjectiveFunction::TerminalUplink),
+            // 151 is a datasphere
+            Token::CollectableItemID(151),
+            Token::CollectableAllocated(53),
+            Token::CollectableItemSeed(9),
+            // a run with only terminals can't be compared
+            Token::SelectExpedition("R1A1".parse().unwrap(), 6),
+            Token::GeneratingLevel,
+            Token::CollectableAllocated(52),
+            Token::ObjectiveSpawnedOverride(4, ObjectiveFunction::TerminalUplink),
+            Token::SelectExpedition("R1B1".parse().unwrap(), 7),
+            Token::GeneratingLevel,
+            key("BULKHEAD_KEY_3"),
+            Token::ItemSpawn(60, 2),
+        ];
+
+        let runs = ObservedRun::from_tokens(tokens);
+        let seeds: Vec<(String, i32)> = runs.iter().map(|run| (run.level.to_string(), run.seed)).collect();
+        assert_eq!(seeds, [("R1A1".to_owned(), 5), ("R1B1".to_owned(), 7)]);
+
+        assert_eq!(runs[0].locations.len(), 2);
+        assert!(matches!(runs[0].locations[0], Location::ColoredKey(_, 51, 3)));
+        assert!(matches!(runs[0].locations[1], Location::Gatherable(ItemIdentifier::Datasphere, 53, 9)));
+        assert_eq!(runs[1].locations.len(), 1);
+        assert!(matches!(runs[1].locations[0], Location::BulkheadKey(_, 60, 2)));
+    }
+
+    #[test]
+    fn test_is_match() {
+        let item = |name: &str| OutputSeedIndexer::Key(ItemKind::from(name), 0, 53, 9, Default::default());
+        let datasphere = Location::Gatherable(ItemIdentifier::Datasphere, 53, 9);
+
+        assert!(is_match(&datasphere, &item("DATA_SPHERE")));
+        assert!(is_match(&datasphere, &item("GatherSmallItems")));
+        assert!(!is_match(&datasphere, &item("FOG_TURBINE")));
+        assert!(!is_match(&datasphere, &item("HSU_FindTakeSample")));
+        assert!(!is_match(&Location::Gatherable(ItemIdentifier::Datasphere, 53, 8), &item("DATA_SPHERE")));
+        assert!(is_match(&Location::BigCollectable(ItemIdentifier::Cryo, 53), &item("RetrieveBigItems")));
+        assert!(!is_match(&Location::BigCollectable(ItemIdentifier::Cryo, 53), &item("MATTER_WAVE_PROJECTOR")));
+    }
+
+    #[test]
+    fn test_calibrate_finds_descriptor_values() {
+        let levels = test_levels();
+        let level = &levels["R1A1"];
+        let runs = observed_keys(level);
+
+        let expected = expected(level);
+        let result = calibrate(level, &runs, 3..=7, 320..=328, 2, |_, _| {});
+
+        assert!(result.is_exact());
+        assert!(result.best.contains(&expected));
+        assert!(unmatched(level, &runs, expected).is_empty());
+    }
+
+    #[test]
+    fn test_calibrate_without_exact_candidate() {
+        let levels = test_levels();
+        let level = &levels["R1A1"];
+        let mut runs = observed_keys(level);
+        // a key no candidate can place, e.g. one the mapper read wrong
+        runs[1].locations.push(Location::ColoredKey("KEY_RED_1".to_owned(), 9999, 1));
+
+        let expected = expected(level);
+        let result = calibrate(level, &runs, 3..=7, 320..=328, 2, |_, _| {});
+
+        assert!(!result.is_exact());
+        assert_eq!(result.matched, result.total - 1);
+        assert!(result.best.contains(&expected));
+
+        let unmatched = unmatched(level, &runs, expected);
+        assert_eq!(unmatched.len(), 1);
+        assert!(matches!(unmatched[0], (2, Location::ColoredKey(_, 9999, 1))));
+    }
+}
@@ -0,0 +1,510 @@
+//! builds `LevelData` skeletons out of the game's datablock dumps
+//! (`GameData_LevelLayoutDataBlock_bin.json` and friends).
+//!
+//! Only what can be read from the datablocks is filled in, everything that
+//! has to be calibrated against the game is listed in [`LevelSkeleton::todo`].
+
+use std::{collections::HashMap, fs, path::Path};
+
+use glr_core::seed_indexer_result::ResourceType;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::seed_gen::zone_info::{
+    generated_data::AllocType,
+    level_data::{LevelData, StagedObjective},
+    unlock_method::{UnlockMethod, UnlockMethodType, ZoneLocationSpawn},
+    zone_data::ZoneData,
+    zone_identifier::ZoneIdentifier,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DatablockKind {
+    LevelLayout,
+    ExpeditionBalance,
+    WardenObjective,
+    ConsumableDistribution,
+}
+
+impl DatablockKind {
+    const ALL: [DatablockKind; 4] = [
+        DatablockKind::LevelLayout,
+        DatablockKind::ExpeditionBalance,
+        DatablockKind::WardenObjective,
+        DatablockKind::ConsumableDistribution,
+    ];
+
+    fn file_marker(&self) -> &'static str {
+        match self {
+            DatablockKind::LevelLayout => "LevelLayoutDataBlock",
+            DatablockKind::ExpeditionBalance => "ExpeditionBalanceDataBlock",
+            DatablockKind::WardenObjective => "WardenObjectiveDataBlock",
+            DatablockKind::ConsumableDistribution => "ConsumableDistributionDataBlock",
+        }
+    }
+}
+
+/// the `Blocks` of every dump that was found
+#[derive(Debug, Default)]
+pub struct Datablocks {
+    blocks: HashMap<DatablockKind, Vec<Value>>,
+}
+
+impl Datablocks {
+    /// loads every known dump in `dir`. A dump is recognized by its file
+    /// name containing the datablock name, e.g. `LevelLayoutDataBlock`.
+    pub fn load_dir(dir: &Path) -> Result<Self, String> {
+        let mut result = Self::default();
+
+        for entry in fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(kind) = DatablockKind::ALL.into_iter().find(|k| name.contains(k.file_marker())) else {
+                continue;
+            };
+
+            let text = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            result.insert_dump(kind, &text).map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+
+        Ok(result)
+    }
+
+    pub fn insert_dump(&mut self, kind: DatablockKind, json: &str) -> Result<(), String> {
+        let dump: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let blocks = dump
+            .get("Blocks")
+            .and_then(Value::as_array)
+            .ok_or("dump has no Blocks array")?;
+
+        self.blocks.entry(kind).or_default().extend(blocks.iter().cloned());
+        Ok(())
+    }
+
+    pub fn get(&self, kind: DatablockKind, persistent_id: u64) -> Option<&Value> {
+        self.blocks
+            .get(&kind)?
+            .iter()
+            .find(|b| b.get("persistentID").and_then(Value::as_u64) == Some(persistent_id))
+    }
+}
+
+/// the datablocks of one layer of the expedition
+#[derive(Debug, Clone, Copy)]
+pub struct LayerSource {
+    pub layout: u64,
+    pub objective: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct SkeletonSource {
+    pub build_seed: Option<i32>,
+    pub balance: Option<u64>,
+    /// main, secondary and overload
+    pub layers: [Option<LayerSource>; 3],
+}
+
+/// serializes as the level entry of `level_descriptors.json` with the
+/// remaining work under `todo` (which is ignored when the level is loaded)
+#[derive(Serialize)]
+pub struct LevelSkeleton {
+    #[serde(flatten)]
+    pub level: LevelData,
+    /// descriptor paths that still need to be filled in by hand
+    pub todo: Vec<String>,
+}
+
+fn get_f32(value: &Value, key: &str) -> Option<f32> {
+    value.get(key)?.as_f64().map(|v| v as f32)
+}
+
+fn get_u64(value: &Value, key: &str) -> Option<u64> {
+    let v = value.get(key)?;
+    v.as_u64().or_else(|| v.as_f64().map(|v| v as u64))
+}
+
+/// enums are dumped either as their number or their name depending on the dumper
+fn get_enum(value: &Value, key: &str, names: &[&str]) -> Option<usize> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_u64().map(|n| n as usize),
+        Value::String(s) => names.iter().position(|n| n == s),
+        _ => None,
+    }
+}
+
+/// `eProgressionPuzzleType`
+const PUZZLE_TYPES: [&str; 4] = ["None", "Keycard_SecurityBox", "PowerGenerator_And_PowerCell", "Locked_No_Key"];
+
+/// `eWardenObjectiveType`, named like the `staged_objectives` of the descriptors
+const OBJECTIVE_TYPES: [&str; 16] = [
+    "HSU_FindTakeSample",
+    "Reactor_Startup",
+    "Reactor_Shutdown",
+    "GatherSmallItems",
+    "ClearAPath",
+    "SpecialTerminalCommand",
+    "RetrieveBigItems",
+    "PowerCellDistribution",
+    "TerminalUplink",
+    "CentralGeneratorCluster",
+    "ActivateSmallHSU",
+    "Survival",
+    "GatherTerminal",
+    "CorruptedTerminalUplink",
+    "Empty",
+    "TimedTerminalSequence",
+];
+
+struct SkeletonBuilder<'a> {
+    blocks: &'a Datablocks,
+    balance: Option<&'a Value>,
+    todo: Vec<String>,
+}
+
+impl SkeletonBuilder<'_> {
+    /// the descriptors only take whole weights, others are rounded and left to be checked
+    fn weight(&mut self, value: Option<f32>, path: impl FnOnce() -> String) -> i32 {
+        let value = value.unwrap_or_default();
+        if value.fract() != 0f32 {
+            self.todo.push(format!("{} ({value} rounded)", path()));
+        }
+
+        value.round() as i32
+    }
+
+    fn weights(&mut self, placement: Option<&Value>, path: &str) -> [i32; 3] {
+        let Some(placement) = placement else {
+            return [0; 3];
+        };
+
+        [("StartWeight", 0), ("MiddleWeight", 1), ("EndWeight", 2)]
+            .map(|(key, i)| self.weight(get_f32(placement, key), || format!("{path}[{i}]")))
+    }
+
+    fn resource(&self, zone: &Value, res_type: ResourceType) -> f32 {
+        let (multi, per_zone) = match res_type {
+            ResourceType::Healthpack => ("HealthMulti", "HealthPerZone"),
+            ResourceType::DisinfectPack => ("DisinfectionMulti", "DisinfectionPerZone"),
+            ResourceType::Ammopack => ("WeaponAmmoMulti", "WeaponAmmoPerZone"),
+            ResourceType::ToolRefillpack => ("ToolAmmoMulti", "ToolAmmoPerZone"),
+        };
+        let per_zone = self.balance.and_then(|b| get_f32(b, per_zone)).unwrap_or(1f32);
+
+        get_f32(zone, multi).unwrap_or(1f32) * per_zone
+    }
+
+    fn zone(&mut self, layout: &Value, zone: &Value, layer: u8) -> Option<ZoneData> {
+        let alias_start = get_u64(layout, "ZoneAliasStart").unwrap_or_default() as i32;
+        let alias_of = |local_index: i32| {
+            layout
+                .get("Zones")
+                .and_then(Value::as_array)
+                .and_then(|zones| zones.iter().find(|z| get_u64(z, "LocalIndex") == Some(local_index as u64)))
+                .and_then(|z| z.get("AliasOverride")?.as_i64())
+                .filter(|alias| *alias >= 0)
+                .map(|alias| alias as i32)
+                .unwrap_or(alias_start + local_index)
+        };
+
+        let zone_id = ZoneIdentifier {
+            layer_id: layer,
+            dimension_id: 0,
+            zone_id: alias_of(get_u64(zone, "LocalIndex")? as i32),
+        };
+
+        let puzzle = zone.get("ProgressionPuzzleToEnter");
+        let unlock_type = match puzzle.and_then(|p| get_enum(p, "PuzzleType", &PUZZLE_TYPES)) {
+            Some(1) => UnlockMethodType::ColoredKey,
+            Some(2) => UnlockMethodType::Cell,
+            Some(0) | Some(3) | None => UnlockMethodType::None,
+            Some(_) => {
+                self.todo.push(format!("zone {zone_id} unlocked_by.unlock_type"));
+                UnlockMethodType::None
+            }
+        };
+
+        let mut unlock_zones = Vec::new();
+        let placements = puzzle
+            .and_then(|p| p.get("ZonePlacementData"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for placement in placements.iter().filter(|_| unlock_type != UnlockMethodType::None) {
+            // other dimensions have their own layout which is not known here
+            if get_u64(placement, "DimensionIndex").unwrap_or_default() != 0 {
+                self.todo.push(format!("zone {zone_id} unlocked_by.zones (placement in another dimension)"));
+                continue;
+            }
+
+            let w = placement.get("Weights");
+            let index = unlock_zones.len();
+            let [start_weight, middle_weight, end_weight] =
+                [("Start", "start"), ("Middle", "middle"), ("End", "end")].map(|(key, field)| {
+                    self.weight(w.and_then(|w| get_f32(w, key)), || {
+                        format!("zone {zone_id} unlocked_by.zones[{index}].{field}_weight")
+                    })
+                });
+            unlock_zones.push(ZoneLocationSpawn {
+                zone_id: ZoneIdentifier {
+                    layer_id: layer,
+                    dimension_id: 0,
+                    zone_id: alias_of(get_u64(placement, "LocalIndex").unwrap_or_default() as i32),
+                },
+                start_weight,
+                middle_weight,
+                end_weight,
+            });
+        }
+
+        let consumables = get_u64(zone, "ConsumableDistributionInZone")
+            .filter(|id| *id != 0)
+            .and_then(|id| self.blocks.get(DatablockKind::ConsumableDistribution, id));
+        let (consumable_count, chance_box_consumable) = match consumables {
+            Some(c) => (
+                get_u64(c, "SpawnsPerZone").unwrap_or_default() as u16,
+                get_f32(c, "ChanceToSpawnInResourceContainer").unwrap_or_default(),
+            ),
+            None => {
+                self.todo.push(format!("zone {zone_id} consumable_count"));
+                (0, 0.9f32)
+            }
+        };
+
+        self.todo.push(format!("zone {zone_id} rooms"));
+        self.todo.push(format!("zone {zone_id} artifact_count"));
+        if zone.get("TerminalPlacements").and_then(Value::as_array).is_some_and(|t| !t.is_empty()) {
+            self.todo.push(format!("zone {zone_id} terminals"));
+        }
+        if get_u64(zone, "BigPickupDistributionInZone").is_some_and(|id| id != 0) {
+            self.todo.push(format!("zone {zone_id} big_pickups"));
+        }
+
+        let medi_weights = self.weights(zone.get("HealthPlacement"), &format!("zone {zone_id} medi_weights"));
+        let disi_weights = self.weights(zone.get("DisinfectionPlacement"), &format!("zone {zone_id} disi_weights"));
+        let ammo_weights = self.weights(zone.get("WeaponAmmoPlacement"), &format!("zone {zone_id} ammo_weights"));
+        let tool_weights = self.weights(zone.get("ToolAmmoPlacement"), &format!("zone {zone_id} tool_weights"));
+
+        Some(ZoneData {
+            zone_id,
+            unlocked_by: UnlockMethod {
+                unlock_type,
+                placement_count: puzzle.and_then(|p| get_u64(p, "PlacementCount")).unwrap_or(1) as usize,
+                zones: unlock_zones,
+            },
+            rooms: Vec::new(),
+            terminals: Vec::new(),
+            alloc_other: Vec::new(),
+            medi: self.resource(zone, ResourceType::Healthpack),
+            medi_weights,
+            disi: self.resource(zone, ResourceType::DisinfectPack),
+            disi_weights,
+            ammo: self.resource(zone, ResourceType::Ammopack),
+            ammo_weights,
+            tool: self.resource(zone, ResourceType::ToolRefillpack),
+            tool_weights,
+            consumable_count,
+            artifact_count: 0,
+            small_pickups: Vec::new(),
+            big_pickups: Vec::new(),
+            chance_box_consumable,
+            allow_big_pickups: false,
+            allow_small_pickups: true,
+            allow_containers_alloc: true,
+            build_seed_spawners_before: 0,
+            build_seed_spawners_after: 0,
+        })
+    }
+
+    fn objective(&mut self, objective: Option<&Value>, index: usize) -> StagedObjective {
+        let type_id = objective.and_then(|o| get_enum(o, "Type", &OBJECTIVE_TYPES));
+        let name = type_id.and_then(|id| OBJECTIVE_TYPES.get(id)).copied().unwrap_or("Empty");
+        if objective.is_some() && type_id.is_none() {
+            self.todo.push(format!("staged_objectives[{index}] unknown objective type"));
+        }
+
+        let (spawn_type, spawn_in_layer) = match name {
+            "HSU_FindTakeSample" | "PowerCellDistribution" => (Some(AllocType::Other), true),
+            "ActivateSmallHSU" => (Some(AllocType::Other), false),
+            "GatherSmallItems" => (Some(AllocType::Container), false),
+            "RetrieveBigItems" | "CentralGeneratorCluster" => (Some(AllocType::BigPickup), false),
+            "SpecialTerminalCommand" | "TerminalUplink" | "CorruptedTerminalUplink" | "GatherTerminal"
+            | "TimedTerminalSequence" => (Some(AllocType::Terminal), true),
+            _ => (None, false),
+        };
+
+        let field = |key: &str| objective.and_then(|o| get_u64(o, key));
+        let count = match name {
+            "GatherSmallItems" => field("GatherSpawnCount"),
+            "RetrieveBigItems" => objective
+                .and_then(|o| o.get("Retrieve_Items")?.as_array())
+                .map(|items| items.len() as u64),
+            "PowerCellDistribution" => field("PowerCellsToDistribute"),
+            // the game's field name has this typo
+            "CentralGeneratorCluster" => field("CentralPowerGenClustser_NumberOfPowerCells")
+                .or_else(|| field("CentralPowerGenCluster_NumberOfPowerCells")),
+            "TerminalUplink" | "CorruptedTerminalUplink" => field("Uplink_NumberOfTerminals"),
+            "GatherTerminal" => field("GatherTerminal_SpawnCount"),
+            _ if spawn_type.is_some() => Some(1),
+            _ => Some(0),
+        };
+        let count = count.unwrap_or_else(|| {
+            self.todo.push(format!("staged_objectives[{index}]({name}).count"));
+            1
+        });
+
+        if count > 0 {
+            // the zones are picked per expedition in the rundown datablock
+            self.todo.push(format!("staged_objectives[{index}]({name}).locations"));
+        }
+
+        StagedObjective {
+            locations: Vec::new(),
+            name: name.to_owned(),
+            spawn_type,
+            count: count as usize,
+            max_per_zone: match name {
+                "GatherSmallItems" => field("GatherMaxPerZone").unwrap_or(3) as usize,
+                _ => 1000,
+            },
+            spawn_in_layer,
+            skip_before_alloc: 0,
+            is_bugged: false,
+        }
+    }
+}
+
+/// builds the skeleton of a level from its datablocks.
+/// Fails only if a datablock it was pointed at does not exist.
+///
+/// `skip_start` and `build_seed_gate_count` are not in the datablocks and are
+/// left at 0, they have to be filled in (e.g. with `calibrate`) before the
+/// level is loaded, like everything else in `todo`. The level is marked as
+/// `inferred` as long as `todo` has anything in it.
+pub fn build_skeleton(blocks: &Datablocks, source: &SkeletonSource) -> Result<LevelSkeleton, String> {
+    let balance = match source.balance {
+        Some(id) => Some(
+            blocks
+                .get(DatablockKind::ExpeditionBalance, id)
+                .ok_or(format!("ExpeditionBalance {id} not found"))?,
+        ),
+        None => None,
+    };
+
+    let mut builder = SkeletonBuilder {
+        blocks,
+        balance,
+        todo: vec!["skip_start".to_owned(), "build_seed_gate_count".to_owned()],
+    };
+    if source.build_seed.is_none() {
+        builder.todo.push("build_seed".to_owned());
+    }
+
+    let mut zones = Vec::new();
+    let mut staged_objectives = Vec::new();
+    let last_layer = source.layers.iter().rposition(Option::is_some).unwrap_or_default();
+
+    for (layer, layer_source) in source.layers.iter().enumerate().take(last_layer + 1) {
+        // objectives are generated by layer index, so skipped layers still need one
+        let Some(layer_source) = layer_source else {
+            staged_objectives.push(builder.objective(None, layer));
+            continue;
+        };
+
+        let layout = blocks
+            .get(DatablockKind::LevelLayout, layer_source.layout)
+            .ok_or(format!("LevelLayout {} not found", layer_source.layout))?;
+        for zone in layout.get("Zones").and_then(Value::as_array).cloned().unwrap_or_default() {
+            if let Some(zone) = builder.zone(layout, &zone, layer as u8) {
+                zones.push(zone);
+            }
+        }
+
+        let objective = match layer_source.objective {
+            Some(id) => Some(
+                blocks
+                    .get(DatablockKind::WardenObjective, id)
+                    .ok_or(format!("WardenObjective {id} not found"))?,
+            ),
+            None => None,
+        };
+        staged_objectives.push(builder.objective(objective, layer));
+    }
+
+    builder.todo.push("bulk_keys_main, bulk_keys_sec, bulk_keys_ovrl".to_owned());
+
+    Ok(LevelSkeleton {
+        level: LevelData {
+            build_seed: source.build_seed.unwrap_or_default(),
+            build_seed_gate_count: 0,
+            skip_start: 0,
+            zones,
+            bulk_keys_main: Vec::new(),
+            bulk_keys_sec: Vec::new(),
+            bulk_keys_ovrl: Vec::new(),
+            staged_objectives,
+            generation_order: Vec::new(),
+            inferred: !builder.todo.is_empty(),
+            unmodeled_zones: Vec::new(),
+        },
+        todo: builder.todo,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skeleton_from_dumps() {
+        let mut blocks = Datablocks::default();
+        blocks
+            .insert_dump(
+                DatablockKind::LevelLayout,
+                r#"{"Blocks": [{"persistentID": 10, "ZoneAliasStart": 49, "Zones": [
+                    {"LocalIndex": 0, "AliasOverride": -1, "HealthMulti": 0.5, "WeaponAmmoMulti": 1.0,
+                     "ConsumableDistributionInZone": 3, "ProgressionPuzzleToEnter": {"PuzzleType": 0}},
+                    {"LocalIndex": 1, "AliasOverride": -1, "HealthPlacement": {"StartWeight": 0.0, "MiddleWeight": 1.6, "EndWeight": 5.0},
+                     "ProgressionPuzzleToEnter": {"PuzzleType": "Keycard_SecurityBox", "PlacementCount": 1,
+                        "ZonePlacementData": [{"DimensionIndex": 0, "LocalIndex": 0, "Weights": {"Start": 0.0, "Middle": 0.0, "End": 50.0}}]}}
+                ]}]}"#,
+            )
+            .unwrap();
+        blocks
+            .insert_dump(
+                DatablockKind::ConsumableDistribution,
+                r#"{"Blocks": [{"persistentID": 3, "SpawnsPerZone": 5, "ChanceToSpawnInResourceContainer": 0.9}]}"#,
+            )
+            .unwrap();
+        blocks
+            .insert_dump(DatablockKind::WardenObjective, r#"{"Blocks": [{"persistentID": 7, "Type": 0}]}"#)
+            .unwrap();
+
+        let source = SkeletonSource {
+            build_seed: Some(333),
+            balance: None,
+            layers: [Some(LayerSource { layout: 10, objective: Some(7) }), None, None],
+        };
+        let skeleton = build_skeleton(&blocks, &source).unwrap();
+        let level = &skeleton.level;
+
+        assert_eq!(level.zones.len(), 2);
+        assert_eq!(level.zones[0].zone_id.zone_id, 49);
+        assert_eq!(level.zones[0].medi, 0.5);
+        assert_eq!(level.zones[0].consumable_count, 5);
+        assert_eq!(level.zones[1].unlocked_by.unlock_type, UnlockMethodType::ColoredKey);
+        assert_eq!(level.zones[1].unlocked_by.zones[0].zone_id.zone_id, 49);
+        assert_eq!(level.zones[1].unlocked_by.zones[0].end_weight, 50);
+        // weights that aren't whole numbers are rounded and left to be checked
+        assert_eq!(level.zones[1].medi_weights, [0, 2, 5]);
+        assert!(skeleton.todo.contains(&"zone L0 D0 Z50 medi_weights[1] (1.6 rounded)".to_owned()));
+        assert_eq!(level.staged_objectives[0].name, "HSU_FindTakeSample");
+
+        assert!(skeleton.todo.contains(&"zone L0 D0 Z50 rooms".to_owned()));
+        assert!(skeleton.todo.contains(&"zone L0 D0 Z50 consumable_count".to_owned()));
+        assert!(!skeleton.todo.contains(&"zone L0 D0 Z49 consumable_count".to_owned()));
+        assert!(skeleton.todo.contains(&"staged_objectives[0](HSU_FindTakeSample).locations".to_owned()));
+        assert!(skeleton.todo.contains(&"skip_start".to_owned()) && level.inferred);
+    }
+}
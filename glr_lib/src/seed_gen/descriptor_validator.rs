@@ -0,0 +1,477 @@
+//! semantic checks for `level_descriptors.json`.
+//!
+//! This file is also included by `build.rs` so it can only depend on
+//! `std` and `serde_json`, which is why it works on `serde_json::Value`
+//! instead of the `LevelData` structs.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ZoneKey {
+    pub layer: u8,
+    pub dimension: u8,
+    pub zone: i32,
+}
+
+impl ZoneKey {
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(Self {
+            layer: value.get("layer_id")?.as_u64()? as u8,
+            dimension: value.get("dimension_id")?.as_u64()? as u8,
+            zone: value.get("zone_id")?.as_i64()? as i32,
+        })
+    }
+}
+
+impl Display for ZoneKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "L{} D{} Z{}", self.layer, self.dimension, self.zone)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub level: String,
+    pub zone: Option<ZoneKey>,
+    /// path to the field inside the level (or zone if `zone` is set)
+    pub field: String,
+    pub message: String,
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        match &self.zone {
+            Some(zone) => write!(f, "{severity}: {} zone {zone} {}: {}", self.level, self.field, self.message),
+            None => write!(f, "{severity}: {} {}: {}", self.level, self.field, self.message),
+        }
+    }
+}
+
+fn spawn_weights(value: &Value) -> [i64; 3] {
+    ["start_weight", "middle_weight", "end_weight"]
+        .map(|k| value.get(k).and_then(Value::as_i64).unwrap_or_default())
+}
+
+fn array_len(value: &Value, field: &str) -> usize {
+    value.get(field).and_then(Value::as_array).map(Vec::len).unwrap_or_default()
+}
+
+/// how much of the start, middle and end weight the `area`th of `areas` areas gets,
+/// the same split `GeneratedZone` uses
+fn multipliers(area: usize, areas: usize) -> [f64; 3] {
+    if area * 2 == areas - 1 {
+        return [0.0, 1.0, 0.0];
+    }
+
+    let half = (areas / 2) as f64;
+    if area < areas / 2 {
+        let start = (half - area as f64) / half;
+        return [start, 1.0 - start, 0.0];
+    }
+
+    let [start, middle, end] = multipliers(areas - area - 1, areas);
+    [end, middle, start]
+}
+
+const ROOM_SIZES: [&str; 5] = ["Tiny", "Small", "Medium", "Large", "Huge"];
+
+struct LevelValidator<'a> {
+    level: &'a str,
+    /// the zones and the numbers of areas their allocations are spread over
+    zones: HashMap<ZoneKey, Vec<usize>>,
+    /// zones the level uses that are in a part of it that isn't described
+    unmodeled: HashSet<ZoneKey>,
+    dimensions: HashSet<u8>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a> LevelValidator<'a> {
+    fn push(&mut self, severity: Severity, zone: Option<ZoneKey>, field: String, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            level: self.level.to_owned(),
+            zone,
+            field,
+            message,
+        });
+    }
+
+    fn check_location(&mut self, zone: Option<ZoneKey>, field: String, location: &Value) {
+        let Some(target) = location.get("zone_id").and_then(ZoneKey::from_value) else {
+            self.push(Severity::Error, zone, field, "location has no valid zone_id".to_owned());
+            return;
+        };
+
+        match self.zones.get(&target).cloned() {
+            Some(areas) => self.check_weights(zone, field, spawn_weights(location), &areas),
+            // the roll still uses its seed, there is just nothing to place it in
+            None if self.unmodeled.contains(&target) => {}
+            None => self.push(
+                Severity::Error,
+                zone,
+                format!("{field}.zone_id"),
+                format!("zone {target} does not exist in zones, add it to unmodeled_zones if it isn't described on purpose"),
+            ),
+        }
+    }
+
+    /// the `i`th of `n` areas gets `weights · multipliers(i, n) + 1` (times its free spots).
+    /// If no area is left with a positive weight nothing can be picked, and an area with
+    /// a negative one shrinks the chances of the areas after it instead of its own.
+    fn check_weights(&mut self, zone: Option<ZoneKey>, field: String, weights: [i64; 3], areas: &[usize]) {
+        for &n in areas {
+            let area_weights: Vec<f64> = (0..n)
+                .map(|i| {
+                    let multipliers = multipliers(i, n);
+                    (0..3).map(|k| multipliers[k] * weights[k] as f64).sum::<f64>() + 1.0
+                })
+                .collect();
+
+            if area_weights.iter().all(|w| *w <= 0.0) {
+                self.push(
+                    Severity::Error,
+                    zone,
+                    field,
+                    format!("weights {weights:?} give all {n} areas a weight of 0 or less"),
+                );
+                return;
+            }
+            if let Some((i, w)) = area_weights.iter().enumerate().find(|(_, w)| **w < 0.0) {
+                self.push(
+                    Severity::Warning,
+                    zone,
+                    field,
+                    format!("weights {weights:?} give area {i} of {n} a negative weight ({w})"),
+                );
+                return;
+            }
+        }
+    }
+
+    /// every room is one of the sizes or `{"Other": [containers, small pickups, big pickups]}`.
+    /// Returns how many rooms the zone has.
+    fn check_rooms(&mut self, zone: Option<ZoneKey>, zone_value: &Value) -> usize {
+        let rooms = zone_value.get("rooms").and_then(Value::as_array).cloned().unwrap_or_default();
+        if rooms.is_empty() {
+            self.push(Severity::Warning, zone, "rooms".to_owned(), "zone has no rooms".to_owned());
+        }
+
+        for (i, room) in rooms.iter().enumerate() {
+            let field = format!("rooms[{i}]");
+
+            if room.as_str().is_some_and(|size| ROOM_SIZES.contains(&size)) {
+                continue;
+            }
+            let Some(counts) = room.get("Other") else {
+                self.push(
+                    Severity::Error,
+                    zone,
+                    field,
+                    format!("{room} is not one of {} or Other", ROOM_SIZES.join(", ")),
+                );
+                continue;
+            };
+
+            let counts: Vec<u64> = counts
+                .as_array()
+                .map(|counts| counts.iter().filter_map(Value::as_u64).filter(|c| *c <= u8::MAX as u64).collect())
+                .unwrap_or_default();
+            match counts.as_slice() {
+                [0, 0, 0] => self.push(
+                    Severity::Warning,
+                    zone,
+                    format!("{field}.Other"),
+                    "room has no containers, small pickups or big pickups".to_owned(),
+                ),
+                [_, _, _] => {}
+                _ => self.push(
+                    Severity::Error,
+                    zone,
+                    format!("{field}.Other"),
+                    "expected the number of containers, small pickups and big pickups (0 to 255)".to_owned(),
+                ),
+            }
+        }
+
+        // each entry is the number of spots in one of the rooms
+        for field in ["terminals", "alloc_other"] {
+            let spots = zone_value.get(field).and_then(Value::as_array).cloned().unwrap_or_default();
+
+            for (i, count) in spots.iter().enumerate() {
+                if count.as_u64().is_none_or(|c| c > u8::MAX as u64) {
+                    self.push(Severity::Error, zone, format!("{field}[{i}]"), format!("{count} is not a number of spots (0 to 255)"));
+                }
+            }
+            if spots.len() > rooms.len() {
+                self.push(
+                    Severity::Warning,
+                    zone,
+                    format!("{field}[{}..]", rooms.len()),
+                    format!("has {} entries but the zone only has {} rooms", spots.len(), rooms.len()),
+                );
+            }
+        }
+
+        rooms.len()
+    }
+
+    fn check_zone(&mut self, zone_value: &Value, index: usize) {
+        let Some(zone) = zone_value.get("zone_id").and_then(ZoneKey::from_value) else {
+            self.push(Severity::Error, None, format!("zones[{index}].zone_id"), "zone has no valid zone_id".to_owned());
+            return;
+        };
+        let areas = self.zones.get(&zone).cloned().unwrap_or_default();
+        let zone = Some(zone);
+
+        let rooms = self.check_rooms(zone, zone_value);
+
+        // resources are only spread over the rooms
+        for field in ["medi_weights", "disi_weights", "ammo_weights", "tool_weights"] {
+            if let Some(weights) = zone_value.get(field).and_then(Value::as_array) {
+                let weights = [0, 1, 2].map(|i| weights.get(i).and_then(Value::as_i64).unwrap_or_default());
+                self.check_weights(zone, field.to_owned(), weights, &[rooms]);
+            }
+        }
+
+        for field in ["small_pickups", "big_pickups"] {
+            let spawns = zone_value.get(field).and_then(Value::as_array).cloned().unwrap_or_default();
+            for (i, spawn) in spawns.iter().enumerate() {
+                self.check_weights(zone, format!("{field}[{i}]"), spawn_weights(spawn), &areas);
+            }
+        }
+
+        let Some(unlocked_by) = zone_value.get("unlocked_by") else {
+            return;
+        };
+        let unlock_type = unlocked_by.get("unlock_type").and_then(Value::as_str).unwrap_or("None");
+        let locations = unlocked_by.get("zones").and_then(Value::as_array).cloned().unwrap_or_default();
+
+        // cells with no placements are spawned by something else so they don't need zones
+        let placement_count = unlocked_by.get("placement_count").and_then(Value::as_u64).unwrap_or(1);
+        if unlock_type != "None" && placement_count > 0 && locations.is_empty() {
+            self.push(
+                Severity::Error,
+                zone,
+                "unlocked_by.zones".to_owned(),
+                format!("unlock type {unlock_type} has no zones to spawn in"),
+            );
+        }
+        for (i, location) in locations.iter().enumerate() {
+            self.check_location(zone, format!("unlocked_by.zones[{i}]"), location);
+        }
+    }
+
+    fn check_level(&mut self, level: &Value) {
+        let todo = level.get("todo").and_then(Value::as_array).map(Vec::len).unwrap_or_default();
+        if todo > 0 {
+            self.push(
+                Severity::Warning,
+                None,
+                "todo".to_owned(),
+                format!("level was generated by datablock_skeleton and still has {todo} fields to fill in"),
+            );
+        }
+
+        let zones = level.get("zones").and_then(Value::as_array).cloned().unwrap_or_default();
+        for (i, zone) in zones.iter().enumerate() {
+            self.check_zone(zone, i);
+        }
+
+        for field in ["bulk_keys_main", "bulk_keys_sec", "bulk_keys_ovrl"] {
+            let lists = level.get(field).and_then(Value::as_array).cloned().unwrap_or_default();
+
+            for (i, list) in lists.iter().enumerate() {
+                let locations = list.as_array().cloned().unwrap_or_default();
+                if locations.is_empty() {
+                    self.push(Severity::Warning, None, format!("{field}[{i}]"), "bulk key has no zones and is skipped".to_owned());
+                    continue;
+                }
+
+                // only the first entry decides which dimension the key is generated in
+                if let Some(dim) = locations[0]
+                    .get("zone_id")
+                    .and_then(ZoneKey::from_value)
+                    .map(|z| z.dimension)
+                    .filter(|dim| !self.dimensions.contains(dim))
+                {
+                    self.push(
+                        Severity::Error,
+                        None,
+                        format!("{field}[{i}]"),
+                        format!("bulk key is in dimension {dim} which has no zones"),
+                    );
+                }
+
+                for (j, location) in locations.iter().enumerate() {
+                    self.check_location(None, format!("{field}[{i}][{j}]"), location);
+                }
+            }
+        }
+
+        let objectives = level.get("staged_objectives").and_then(Value::as_array).cloned().unwrap_or_default();
+
+        let steps = level.get("generation_order").and_then(Value::as_array).cloned().unwrap_or_default();
+        for (i, step) in steps.iter().enumerate() {
+            let ids = step.get("objectives").and_then(Value::as_array).cloned().unwrap_or_default();
+            for (j, id) in ids.iter().enumerate() {
+                if id.as_u64().is_none_or(|id| id as usize >= objectives.len()) {
+                    self.push(
+                        Severity::Error,
+                        None,
+                        format!("generation_order[{i}].objectives[{j}]"),
+                        format!("{id} is not an index of staged_objectives"),
+                    );
+                }
+            }
+        }
+        for (i, objective) in objectives.iter().enumerate() {
+            let name = objective.get("name").and_then(Value::as_str).unwrap_or_default();
+            let locations = objective.get("locations").and_then(Value::as_array).cloned().unwrap_or_default();
+
+            for (j, list) in locations.iter().enumerate() {
+                for (k, location) in list.as_array().cloned().unwrap_or_default().iter().enumerate() {
+                    self.check_location(None, format!("staged_objectives[{i}]({name}).locations[{j}][{k}]"), location);
+                }
+            }
+        }
+    }
+}
+
+pub fn validate_level(name: &str, level: &Value) -> Vec<ValidationIssue> {
+    let zones: HashMap<ZoneKey, Vec<usize>> = level
+        .get("zones")
+        .and_then(Value::as_array)
+        .map(|zones| {
+            zones
+                .iter()
+                .filter_map(|z| {
+                    let mut areas: Vec<usize> = ["rooms", "terminals", "alloc_other"]
+                        .iter()
+                        .map(|field| array_len(z, field))
+                        .filter(|n| *n > 0)
+                        .collect();
+                    areas.sort();
+                    areas.dedup();
+
+                    Some((z.get("zone_id").and_then(ZoneKey::from_value)?, areas))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // `LevelData::unmodeled_zones`, only read here
+    let unmodeled: Vec<Value> = level.get("unmodeled_zones").and_then(Value::as_array).cloned().unwrap_or_default();
+
+    let mut validator = LevelValidator {
+        level: name,
+        dimensions: zones.keys().map(|z| z.dimension).collect(),
+        unmodeled: unmodeled.iter().filter_map(ZoneKey::from_value).collect(),
+        zones,
+        issues: Vec::new(),
+    };
+    for (i, zone) in unmodeled.iter().enumerate() {
+        match ZoneKey::from_value(zone) {
+            Some(zone) if validator.zones.contains_key(&zone) => validator.push(
+                Severity::Warning,
+                None,
+                format!("unmodeled_zones[{i}]"),
+                format!("zone {zone} is in zones"),
+            ),
+            Some(_) => {}
+            None => validator.push(Severity::Error, None, format!("unmodeled_zones[{i}]"), "not a zone_id".to_owned()),
+        }
+    }
+    validator.check_level(level);
+
+    validator.issues
+}
+
+/// validates every level in the json. Fails only if the json itself can't be read.
+pub fn validate_descriptors(json: &str) -> Result<Vec<ValidationIssue>, String> {
+    let levels: BTreeMap<String, Value> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+    Ok(levels
+        .iter()
+        .flat_map(|(name, level)| validate_level(name, level))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn zone_id(zone: i32) -> Value {
+        json!({ "layer_id": 0, "dimension_id": 0, "zone_id": zone })
+    }
+
+    fn location(zone: i32, weights: [i64; 3]) -> Value {
+        json!({ "zone_id": zone_id(zone), "start_weight": weights[0], "middle_weight": weights[1], "end_weight": weights[2] })
+    }
+
+    fn issues(level: &Value) -> Vec<(Severity, String)> {
+        validate_level("R1A1", level).into_iter().map(|v| (v.severity, v.field)).collect()
+    }
+
+    #[test]
+    fn test_validate_level() {
+        let mut level = json!({
+            "zones": [{
+                "zone_id": zone_id(1),
+                "rooms": ["Small", { "Other": [1, 2, 3] }, "Huge"],
+                "terminals": [1, 0],
+                "medi_weights": [0, 0, 0],
+            }],
+            "staged_objectives": [{ "name": "TerminalUplink", "locations": [[location(1, [0, 0, 0])]] }],
+        });
+        assert_eq!(issues(&level), vec![]);
+
+        // the 2nd of 3 rooms only gets the middle weight
+        level["zones"][0]["medi_weights"] = json!([5, -2, 5]);
+        assert_eq!(issues(&level), vec![(Severity::Warning, "medi_weights".to_owned())]);
+        level["zones"][0]["medi_weights"] = json!([-1, -1, -1]);
+        assert_eq!(issues(&level), vec![(Severity::Error, "medi_weights".to_owned())]);
+        level["zones"][0]["medi_weights"] = json!([0, 0, 0]);
+
+        level["zones"][0]["rooms"] = json!(["Small", { "Other": [0, 0, 0] }, "Giant", { "Other": [1, 300] }]);
+        level["zones"][0]["terminals"] = json!([1, 0, 1, 1, 1]);
+        assert_eq!(
+            issues(&level),
+            vec![
+                (Severity::Warning, "rooms[1].Other".to_owned()),
+                (Severity::Error, "rooms[2]".to_owned()),
+                (Severity::Error, "rooms[3].Other".to_owned()),
+                (Severity::Warning, "terminals[4..]".to_owned()),
+            ]
+        );
+        level["zones"][0]["rooms"] = json!(["Small"]);
+        level["zones"][0]["terminals"] = json!([]);
+
+        // missing zones are errors unless they are listed as not described
+        level["staged_objectives"][0]["locations"][0][0] = location(2, [0, 0, 0]);
+        let field = "staged_objectives[0](TerminalUplink).locations[0][0].zone_id".to_owned();
+        assert_eq!(issues(&level), vec![(Severity::Error, field)]);
+        level["unmodeled_zones"] = json!([zone_id(2)]);
+        assert_eq!(issues(&level), vec![]);
+        level["unmodeled_zones"] = json!([zone_id(2), zone_id(1)]);
+        assert_eq!(issues(&level), vec![(Severity::Warning, "unmodeled_zones[1]".to_owned())]);
+    }
+}
@@ -0,0 +1,440 @@
+use std::{collections::HashMap, fmt::Write, ops::RangeInclusive, sync::Mutex};
+
+use glr_core::seed_indexer_result::{ItemKind, LockState, OutputSeedIndexer, ResourceType};
+use serde::Serialize;
+
+use crate::{
+    dll_exports::csv_record::escape,
+    seed_gen::{seed_finder::scan_seeds, zone_info::level_data::LevelData},
+};
+
+/// how often an item spawned in a specific container over all indexed seeds
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemPlacement {
+    pub item: ItemKind,
+    pub dimension: u8,
+    pub zone: i32,
+    pub id: i32,
+    pub count: u64,
+    pub probability: f64,
+}
+
+/// sum of the pack sizes of one resource type in a zone over all indexed seeds
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneResources {
+    pub dimension: u8,
+    pub zone: i32,
+    pub resource: ResourceType,
+    pub total: u64,
+    pub mean: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutStats {
+    pub level: String,
+    pub seeds: u64,
+    pub expected_locked_containers: f64,
+    pub items: Vec<ItemPlacement>,
+    pub resources: Vec<ZoneResources>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeedComparison {
+    pub seed: i32,
+    pub locked_containers: u64,
+    pub expected_locked_containers: f64,
+    /// every item of the seed with the probability of it spawning in that container
+    pub items: Vec<ItemPlacement>,
+    /// resources of the seed per zone, `mean` is the average of the level
+    pub resources: Vec<ZoneResources>,
+}
+
+#[derive(Debug, Default)]
+struct SeedSummary {
+    items: Vec<(ItemKind, u8, i32, i32)>,
+    locked_containers: u64,
+    resources: HashMap<(u8, i32, ResourceType), u64>,
+}
+
+impl SeedSummary {
+    fn new(layout: &[OutputSeedIndexer]) -> Self {
+        let mut summary = Self::default();
+
+        for v in layout {
+            match v {
+                OutputSeedIndexer::Key(name, dim, zone, id, _) => {
+                    summary.items.push((name.clone(), *dim, *zone, *id))
+                }
+                OutputSeedIndexer::LockStateChange(_, _, _, lock)
+                    if !matches!(lock, LockState::Unlocked) =>
+                {
+                    summary.locked_containers += 1;
+                }
+                OutputSeedIndexer::ResourcePack(res, dim, zone, _, size, ..) => {
+                    *summary.resources.entry((*dim, *zone, *res)).or_default() += *size as u64;
+                }
+                _ => {}
+            }
+        }
+
+        summary
+    }
+}
+
+#[derive(Debug, Default)]
+struct StatsAccumulator {
+    seeds: u64,
+    locked_containers: u64,
+    items: HashMap<(ItemKind, u8, i32, i32), u64>,
+    resources: HashMap<(u8, i32, ResourceType), u64>,
+}
+
+impl StatsAccumulator {
+    fn add(&mut self, summary: SeedSummary) {
+        self.seeds += 1;
+        self.locked_containers += summary.locked_containers;
+
+        for item in summary.items {
+            *self.items.entry(item).or_default() += 1;
+        }
+        for (key, total) in summary.resources {
+            *self.resources.entry(key).or_default() += total;
+        }
+    }
+
+    fn merge(&mut self, other: StatsAccumulator) {
+        self.seeds += other.seeds;
+        self.locked_containers += other.locked_containers;
+
+        for (key, count) in other.items {
+            *self.items.entry(key).or_default() += count;
+        }
+        for (key, total) in other.resources {
+            *self.resources.entry(key).or_default() += total;
+        }
+    }
+
+    fn finish(self, level: &str) -> LayoutStats {
+        let seeds = self.seeds.max(1) as f64;
+
+        let mut items: Vec<ItemPlacement> = self
+            .items
+            .into_iter()
+            .map(|((item, dimension, zone, id), count)| ItemPlacement {
+                item,
+                dimension,
+                zone,
+                id,
+                count,
+                probability: count as f64 / seeds,
+            })
+            .collect();
+        items.sort_by(|a, b| {
+            (&a.item, a.dimension, a.zone, a.id).cmp(&(&b.item, b.dimension, b.zone, b.id))
+        });
+
+        let mut resources: Vec<ZoneResources> = self
+            .resources
+            .into_iter()
+            .map(|((dimension, zone, resource), total)| ZoneResources {
+                dimension,
+                zone,
+                resource,
+                total,
+                mean: total as f64 / seeds,
+            })
+            .collect();
+        resources.sort_by_key(|v| (v.dimension, v.zone, v.resource));
+
+        LayoutStats {
+            level: level.to_owned(),
+            seeds: self.seeds,
+            expected_locked_containers: self.locked_containers as f64 / seeds,
+            items,
+            resources,
+        }
+    }
+}
+
+impl LayoutStats {
+    /// indexes every seed in the range and builds the distributions of the level
+    pub fn collect(
+        level_name: &str,
+        level: &LevelData,
+        seeds: RangeInclusive<i32>,
+        threads: usize,
+        progress: impl Fn(u64, u64) + Sync,
+    ) -> Self {
+        // seeds are spread over a few accumulators so threads rarely wait on each other
+        let shards: Vec<Mutex<StatsAccumulator>> = (0..threads.max(1) * 4)
+            .map(|_| Mutex::default())
+            .collect();
+
+        scan_seeds(
+            level,
+            seeds,
+            threads,
+            |seed, layout| {
+                let shard = &shards[seed.unsigned_abs() as usize % shards.len()];
+                if let Ok(mut acc) = shard.lock() {
+                    acc.add(SeedSummary::new(&layout));
+                }
+                None::<()>
+            },
+            progress,
+        );
+
+        shards
+            .into_iter()
+            .filter_map(|v| v.into_inner().ok())
+            .fold(StatsAccumulator::default(), |mut acc, v| {
+                acc.merge(v);
+                acc
+            })
+            .finish(level_name)
+    }
+
+    pub fn item_probability(&self, item: &ItemKind, dimension: u8, zone: i32, id: i32) -> f64 {
+        self.items
+            .iter()
+            .find(|v| v.item == *item && v.dimension == dimension && v.zone == zone && v.id == id)
+            .map(|v| v.probability)
+            .unwrap_or_default()
+    }
+
+    pub fn resource_mean(&self, dimension: u8, zone: i32, resource: ResourceType) -> f64 {
+        self.resources
+            .iter()
+            .find(|v| v.dimension == dimension && v.zone == zone && v.resource == resource)
+            .map(|v| v.mean)
+            .unwrap_or_default()
+    }
+
+    /// puts the layout of a single seed next to the averages of the level
+    pub fn compare(&self, seed: i32, layout: &[OutputSeedIndexer]) -> SeedComparison {
+        let summary = SeedSummary::new(layout);
+
+        let items = summary
+            .items
+            .into_iter()
+            .map(|(item, dimension, zone, id)| ItemPlacement {
+                probability: self.item_probability(&item, dimension, zone, id),
+                count: 1,
+                item,
+                dimension,
+                zone,
+                id,
+            })
+            .collect();
+
+        // zones where the seed got nothing are still shown so they can be compared
+        let mut resources: Vec<ZoneResources> = self
+            .resources
+            .iter()
+            .map(|v| ZoneResources {
+                total: summary
+                    .resources
+                    .get(&(v.dimension, v.zone, v.resource))
+                    .cloned()
+                    .unwrap_or_default(),
+                ..v.clone()
+            })
+            .collect();
+        resources.extend(
+            summary
+                .resources
+                .iter()
+                .filter(|((dim, zone, res), _)| self.resource_mean(*dim, *zone, *res) == 0.0)
+                .map(|((dimension, zone, resource), total)| ZoneResources {
+                    dimension: *dimension,
+                    zone: *zone,
+                    resource: *resource,
+                    total: *total,
+                    mean: 0.0,
+                }),
+        );
+
+        SeedComparison {
+            seed,
+            locked_containers: summary.locked_containers,
+            expected_locked_containers: self.expected_locked_containers,
+            items,
+            resources,
+        }
+    }
+
+    pub fn items_csv(&self) -> String {
+        let mut result = String::from("item,dimension,zone,id,count,probability\n");
+
+        // objective items are named by the level descriptors, which can use any characters
+        for v in &self.items {
+            let _ = writeln!(
+                result,
+                "{},{},{},{},{},{}",
+                escape(&v.item.to_string()),
+                v.dimension,
+                v.zone,
+                v.id,
+                v.count,
+                v.probability
+            );
+        }
+
+        result
+    }
+
+    pub fn resources_csv(&self) -> String {
+        let mut result = String::from("dimension,zone,resource,total,mean\n");
+
+        for v in &self.resources {
+            let _ = writeln!(
+                result,
+                "{},{},{:?},{},{}",
+                v.dimension, v.zone, v.resource, v.total, v.mean
+            );
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glr_core::seed_indexer_result::Provenance;
+
+    use super::*;
+    use crate::seed_gen::{consumers::base_consumer::Consumer, test_levels, unity_random::UnityRandom};
+
+    const SEEDS: RangeInclusive<i32> = 1..=12;
+
+    fn layouts(level: &LevelData) -> Vec<(i32, Vec<OutputSeedIndexer>)> {
+        SEEDS
+            .map(|seed| {
+                let mut layout = Vec::new();
+                level.take(&mut UnityRandom::from(seed), &mut layout);
+                (seed, layout)
+            })
+            .collect()
+    }
+
+    fn collect(level: &LevelData, threads: usize) -> LayoutStats {
+        LayoutStats::collect("R1A1", level, SEEDS, threads, |_, _| {})
+    }
+
+    #[test]
+    fn test_merge_shards() {
+        let level = &test_levels()["R1A1"];
+        let json = |stats: &LayoutStats| serde_json::to_string(stats).unwrap();
+
+        // the seeds are summed the same whichever shard they went to
+        let mut single = StatsAccumulator::default();
+        let (mut odd, mut even) = (StatsAccumulator::default(), StatsAccumulator::default());
+        for (seed, layout) in layouts(level) {
+            single.add(SeedSummary::new(&layout));
+            match seed % 2 {
+                0 => even.add(SeedSummary::new(&layout)),
+                _ => odd.add(SeedSummary::new(&layout)),
+            }
+        }
+        odd.merge(even);
+
+        let single = single.finish("R1A1");
+        assert_eq!(single.seeds, SEEDS.count() as u64);
+        assert_eq!(json(&odd.finish("R1A1")), json(&single));
+        assert_eq!(json(&collect(level, 1)), json(&single));
+        assert_eq!(json(&collect(level, 3)), json(&single));
+    }
+
+    #[test]
+    fn test_probability_and_mean() {
+        let level = &test_levels()["R1A1"];
+        let stats = collect(level, 2);
+        let layouts = layouts(level);
+        let seeds = layouts.len() as f64;
+        assert!(!stats.items.is_empty() && !stats.resources.is_empty());
+
+        for item in &stats.items {
+            // a container can get the same kind of item more than once
+            let count = layouts
+                .iter()
+                .flat_map(|(_, layout)| layout)
+                .filter(|v| {
+                    matches!(v, OutputSeedIndexer::Key(name, dim, zone, id, _)
+                        if *name == item.item && (*dim, *zone, *id) == (item.dimension, item.zone, item.id))
+                })
+                .count();
+            assert_eq!(item.count, count as u64, "{item:?}");
+            assert_eq!(stats.item_probability(&item.item, item.dimension, item.zone, item.id), count as f64 / seeds);
+        }
+
+        for resource in &stats.resources {
+            let total: u64 = layouts
+                .iter()
+                .flat_map(|(_, layout)| layout)
+                .map(|v| match v {
+                    OutputSeedIndexer::ResourcePack(res, dim, zone, _, size, ..)
+                        if (*res, *dim, *zone) == (resource.resource, resource.dimension, resource.zone) =>
+                    {
+                        *size as u64
+                    }
+                    _ => 0,
+                })
+                .sum();
+            assert_eq!(resource.total, total, "{resource:?}");
+            assert_eq!(stats.resource_mean(resource.dimension, resource.zone, resource.resource), total as f64 / seeds);
+        }
+
+        assert_eq!(stats.item_probability(&ItemKind::Cell, 0, -1, 0), 0.0);
+    }
+
+    #[test]
+    fn test_compare() {
+        let level = &test_levels()["R1A1"];
+        let stats = collect(level, 2);
+        let (seed, mut layout) = layouts(level).swap_remove(0);
+
+        let comparison = stats.compare(seed, &layout);
+        assert!(comparison.items.iter().all(|v| v.probability > 0.0));
+        // every zone of the level is listed, with the seed's own totals
+        assert_eq!(comparison.resources.len(), stats.resources.len());
+        for (own, average) in comparison.resources.iter().zip(&stats.resources) {
+            assert_eq!(
+                (own.dimension, own.zone, own.resource, own.mean),
+                (average.dimension, average.zone, average.resource, average.mean)
+            );
+        }
+
+        // zones the level average doesn't have are added with a mean of 0
+        let pack = OutputSeedIndexer::ResourcePack(ResourceType::Ammopack, 0, 999, 0, 3, 1.0, false, Provenance::default());
+        layout.push(pack);
+        let comparison = stats.compare(seed, &layout);
+        assert_eq!(comparison.resources.len(), stats.resources.len() + 1);
+        let added = comparison.resources.last().unwrap();
+        assert_eq!((added.zone, added.resource, added.total, added.mean), (999, ResourceType::Ammopack, 3, 0.0));
+    }
+
+    #[test]
+    fn test_csv() {
+        let stats = collect(&test_levels()["R1A1"], 2);
+
+        let items = stats.items_csv();
+        let mut lines = items.lines();
+        assert_eq!(lines.next(), Some("item,dimension,zone,id,count,probability"));
+        assert_eq!(lines.count(), stats.items.len());
+        let resources = stats.resources_csv();
+        assert_eq!(resources.lines().next(), Some("dimension,zone,resource,total,mean"));
+        assert_eq!(resources.lines().count(), stats.resources.len() + 1);
+        assert!(resources.lines().skip(1).all(|line| line.split(',').count() == 5));
+
+        // objective names are quoted when they would split the row
+        let mut accumulator = StatsAccumulator::default();
+        accumulator.add(SeedSummary {
+            items: vec![(ItemKind::Objective("HSU, \"Alpha\"".to_owned()), 0, 51, 2)],
+            ..Default::default()
+        });
+        assert_eq!(
+            accumulator.finish("R1A1").items_csv(),
+            "item,dimension,zone,id,count,probability\n\"HSU, \"\"Alpha\"\"\",0,51,2,1,1\n"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, fmt::Write, sync::LazyLock};
+
+use glr_core::seed_indexer_result::OutputSeedIndexer;
+use serde::{Deserialize, Serialize};
+
+use crate::output_trait::OutputTrait;
+
+pub static LAYOUT_VARIANTS: LazyLock<Option<LayoutVariants>> = LazyLock::new(LayoutVariants::load_all);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutVariant {
+    pub name: String,
+    #[serde(default)]
+    pub notes: String,
+    /// whether the layout was checked in game. Only verified variants are
+    /// reported as `KnownLayout`, the others are candidates found offline.
+    #[serde(default)]
+    pub verified: bool,
+}
+
+/// named layout variants of each level, keyed by the hex form of
+/// the `GenerationOverflowHash` of seeds that build that variant
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LayoutVariants {
+    map: BTreeMap<String, BTreeMap<String, LayoutVariant>>,
+}
+
+pub fn hash_to_hex(hash: &[u8; 32]) -> String {
+    hash.iter().fold(String::with_capacity(64), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+impl LayoutVariants {
+    #[cfg(not(debug_assertions))]
+    pub fn load_all() -> Option<Self> {
+        let baked = include_bytes!("../../../interop/layout_variants.bin");
+
+        bincode::deserialize(baked).ok()
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn load_all() -> Option<Self> {
+        let path = std::env::current_dir()
+            .ok()?
+            .join("resources")
+            .join("layout_variants.ron");
+        let text = std::fs::read_to_string(path).ok()?;
+
+        ron::from_str(&text).ok()
+    }
+
+    pub fn get(&self, level_name: &str, hash: &[u8; 32]) -> Option<&LayoutVariant> {
+        self.map.get(level_name)?.get(&hash_to_hex(hash))
+    }
+
+    /// the variant with that hash if it was verified in game
+    pub fn known(&self, level_name: &str, hash: &[u8; 32]) -> Option<&LayoutVariant> {
+        self.get(level_name, hash).filter(|v| v.verified)
+    }
+
+    /// adds or replaces a variant, returns the one it replaced
+    pub fn insert(&mut self, level_name: &str, hash_hex: &str, variant: LayoutVariant) -> Option<LayoutVariant> {
+        self.map
+            .entry(level_name.to_owned())
+            .or_default()
+            .insert(hash_hex.to_ascii_lowercase(), variant)
+    }
+}
+
+/// forwards the seed indexer output and adds a `KnownLayout` after
+/// every `GenerationOverflowHash` that is in the database and verified
+pub struct KnownLayoutOutput<'a, O> {
+    level_name: &'a str,
+    inner: &'a mut O,
+}
+
+impl<'a, O> KnownLayoutOutput<'a, O> {
+    pub fn new(level_name: &'a str, inner: &'a mut O) -> Self {
+        Self { level_name, inner }
+    }
+}
+
+impl<O: OutputTrait<OutputSeedIndexer>> OutputTrait<OutputSeedIndexer> for KnownLayoutOutput<'_, O> {
+    fn output(&mut self, data: OutputSeedIndexer) {
+        let known = match &data {
+            OutputSeedIndexer::GenerationOverflowHash(hash) => LAYOUT_VARIANTS
+                .as_ref()
+                .and_then(|v| v.known(self.level_name, hash))
+                .cloned(),
+            _ => None,
+        };
+
+        self.inner.output(data);
+
+        if let Some(variant) = known {
+            self.inner.output(OutputSeedIndexer::KnownLayout(variant.name, variant.notes));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed_gen::{consumers::base_consumer::Consumer, test_levels, unity_random::UnityRandom};
+
+    #[test]
+    fn test_known_layout() {
+        let variants: LayoutVariants = ron::from_str(include_str!("../../../resources/layout_variants.ron")).unwrap();
+        let levels = test_levels();
+
+        // seed 0 overflows 15 times and seed 9 16 times
+        for (seed, name) in [(0, "A"), (9, "B")] {
+            let mut layout = Vec::new();
+            levels["R1D1"].take(&mut UnityRandom::from(seed), &mut layout);
+
+            let hash = layout
+                .iter()
+                .find_map(|v| match v {
+                    OutputSeedIndexer::GenerationOverflowHash(hash) => Some(*hash),
+                    _ => None,
+                })
+                .unwrap();
+
+            assert_eq!(variants.get("R1D1", &hash).map(|v| v.name.as_str()), Some(name), "seed {seed}");
+            assert!(variants.get("R1D2", &hash).is_none());
+            // they were only found with the seed indexer so far
+            assert!(variants.known("R1D1", &hash).is_none());
+        }
+    }
+
+    #[test]
+    fn test_verified() {
+        let hash = [7; 32];
+        let mut variants = LayoutVariants::default();
+        let variant = |verified| LayoutVariant { name: "A".to_owned(), notes: String::new(), verified };
+
+        variants.insert("R1D1", &hash_to_hex(&hash), variant(false));
+        assert!(variants.get("R1D1", &hash).is_some());
+        assert!(variants.known("R1D1", &hash).is_none());
+
+        variants.insert("R1D1", &hash_to_hex(&hash), variant(true));
+        assert_eq!(variants.known("R1D1", &hash).map(|v| v.name.as_str()), Some("A"));
+    }
+}
@@ -1,14 +1,32 @@
-use std::{collections::BTreeMap, fmt::Debug};
+use std::{collections::BTreeMap, fmt::Debug, ops::Deref, sync::Arc};
 
 use glr_core::data::LevelDescriptor;
 
-use crate::{load_seed_consumers::SEED_LEVEL_DATAS, seed_gen::zone_info::level_data::LevelData};
+use crate::{load_seed_consumers::{LoadSeedConsumers, SEED_LEVEL_DATAS}, seed_gen::zone_info::level_data::LevelData};
 
 #[derive(Debug)]
 pub struct LevelDescriptors {
     levels: &'static Option<BTreeMap<String, LevelData>>,
 }
 
+/// either a level included in the build or one loaded at runtime as an override
+#[derive(Debug, Clone)]
+pub enum LevelRef {
+    Included(&'static LevelData),
+    Override(Arc<LevelData>),
+}
+
+impl Deref for LevelRef {
+    type Target = LevelData;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            LevelRef::Included(level) => level,
+            LevelRef::Override(level) => level,
+        }
+    }
+}
+
 impl Default for LevelDescriptors {
     fn default() -> Self {
         Self {
@@ -18,8 +36,23 @@ impl Default for LevelDescriptors {
 }
 
 impl LevelDescriptors {
-    pub fn get_level(&self, level: &LevelDescriptor) -> Option<&LevelData> {
+    pub fn get_level(&self, level: &LevelDescriptor) -> Option<LevelRef> {
+        self.get_level_by_name(&level.to_string())
+    }
+
+    pub fn get_level_by_name(&self, level_name: &str) -> Option<LevelRef> {
+        if let Some(level) = LoadSeedConsumers::get_override(level_name) {
+            return Some(LevelRef::Override(level));
+        }
+
         self.levels.as_ref()?
-            .get(&level.to_string())
+            .get(level_name)
+            .map(LevelRef::Included)
+    }
+
+    /// picks up changes to the override files. On error the old overrides are kept
+    /// and the error is available from `LoadSeedConsumers::reload_error`.
+    pub fn refresh_overrides(&self) {
+        let _ = LoadSeedConsumers::reload_overrides();
     }
 }
@@ -1,8 +1,25 @@
 pub mod levels;
 pub mod unity_random;
 
-mod seed_generator;
+pub mod seed_generator;
 
 pub mod consumers;
 pub mod zone_info;
 mod marker_set;
+pub mod calibration;
+pub mod datablock_skeleton;
+pub mod descriptor_validator;
+pub mod layout_stats;
+pub mod layout_variants;
+pub mod seed_finder;
+pub mod seed_trace;
+
+/// the level descriptors shipped with the crate, for tests
+#[cfg(test)]
+pub(crate) const LEVEL_DESCRIPTORS_JSON: &str = include_str!("../../../resources/level_descriptors.json");
+
+/// the shipped levels by name
+#[cfg(test)]
+pub(crate) fn test_levels() -> std::collections::BTreeMap<String, zone_info::level_data::LevelData> {
+    serde_json::from_str(LEVEL_DESCRIPTORS_JSON).unwrap()
+}
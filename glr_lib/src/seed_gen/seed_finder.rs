@@ -0,0 +1,403 @@
+use std::{
+    ops::RangeInclusive,
+    str::FromStr,
+    sync::{
+        Mutex,
+        atomic::{AtomicI64, AtomicU64, Ordering},
+    },
+    thread,
+};
+
+use glr_core::seed_indexer_result::{ItemKind, LockState, OutputSeedIndexer};
+use serde::Serialize;
+
+use crate::seed_gen::{
+    consumers::base_consumer::Consumer, unity_random::UnityRandom,
+    zone_info::level_data::LevelData,
+};
+
+const CHUNK_SIZE: i64 = 256;
+
+/// a single condition the generated layout of a seed has to respect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedConstraint {
+    /// `zone:NAME:ZONE[:MAX_ID]` the item spawns in the zone and optionally
+    /// in a container with an id at most MAX_ID
+    ItemInZone {
+        item: ItemKind,
+        zone: i32,
+        max_id: Option<i32>,
+    },
+    /// `unlocked:NAME[:NTH]` the nth (starting from 0) item with this name
+    /// is not behind a locked container
+    ItemUnlocked { item: ItemKind, nth: usize },
+    /// `overflow:MAX` the level overflowed at most MAX times
+    MaxOverflow(usize),
+}
+
+/// parses one field of the constraint `s` straight into the type of the field
+fn parse_field<T: FromStr>(v: &str, s: &str) -> Result<T, String> {
+    v.parse::<T>()
+        .map_err(|_| format!("'{v}' is not a valid {} in constraint '{s}'", std::any::type_name::<T>()))
+}
+
+impl FromStr for SeedConstraint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+
+        match parts.as_slice() {
+            ["zone", name, zone] => Ok(SeedConstraint::ItemInZone {
+                item: ItemKind::from(*name),
+                zone: parse_field(zone, s)?,
+                max_id: None,
+            }),
+            ["zone", name, zone, max_id] => Ok(SeedConstraint::ItemInZone {
+                item: ItemKind::from(*name),
+                zone: parse_field(zone, s)?,
+                max_id: Some(parse_field(max_id, s)?),
+            }),
+            ["unlocked", name] => Ok(SeedConstraint::ItemUnlocked {
+                item: ItemKind::from(*name),
+                nth: 0,
+            }),
+            ["unlocked", name, nth] => Ok(SeedConstraint::ItemUnlocked {
+                item: ItemKind::from(*name),
+                nth: parse_field(nth, s)?,
+            }),
+            ["overflow", max] => Ok(SeedConstraint::MaxOverflow(parse_field(max, s)?)),
+            _ => Err(format!("Unknown constraint: '{s}'")),
+        }
+    }
+}
+
+/// the result of checking a constraint against a layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ConstraintCheck {
+    Matched,
+    Failed,
+    /// the layout doesn't have what is needed to tell, e.g. the lock state of an item
+    Unknown,
+}
+
+impl From<bool> for ConstraintCheck {
+    fn from(value: bool) -> Self {
+        match value {
+            true => ConstraintCheck::Matched,
+            false => ConstraintCheck::Failed,
+        }
+    }
+}
+
+impl SeedConstraint {
+    pub fn check(&self, layout: &[OutputSeedIndexer]) -> ConstraintCheck {
+        match self {
+            SeedConstraint::ItemInZone { item, zone, max_id } => {
+                layout.iter().any(|v| match v {
+                    OutputSeedIndexer::Key(n, _, z, id, _) => {
+                        n == item && z == zone && max_id.is_none_or(|max| *id <= max)
+                    }
+                    _ => false,
+                }).into()
+            }
+            SeedConstraint::ItemUnlocked { item, nth } => {
+                let Some((dim, zone, id)) = layout
+                    .iter()
+                    .filter_map(|v| match v {
+                        OutputSeedIndexer::Key(n, dim, zone, id, _) if n == item => Some((*dim, *zone, *id)),
+                        _ => None,
+                    })
+                    .nth(*nth)
+                else {
+                    return ConstraintCheck::Failed;
+                };
+
+                // the last lock state output for the container the item is in,
+                // items that are not in a container don't have one
+                let lock = layout.iter().rev().find_map(|v| match v {
+                    OutputSeedIndexer::LockStateChange(d, z, i, lock) if *d == dim && *z == zone && *i == id => {
+                        Some(*lock)
+                    }
+                    _ => None,
+                });
+
+                match lock {
+                    Some(lock) => (lock == LockState::Unlocked).into(),
+                    None => ConstraintCheck::Unknown,
+                }
+            }
+            SeedConstraint::MaxOverflow(max) => {
+                let overflow = layout
+                    .iter()
+                    .find_map(|v| match v {
+                        OutputSeedIndexer::GenerationOverflow(count) => Some(*count),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+
+                (overflow <= *max).into()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeedMatch {
+    pub seed: i32,
+    /// `Unknown` when no constraint failed but some could not be checked
+    pub check: ConstraintCheck,
+    pub layout: Vec<OutputSeedIndexer>,
+}
+
+/// runs the seed indexer for every seed in the range on multiple threads.
+/// `filter` is called for every generated layout and its results are
+/// collected, `progress` is called with (seeds done, total seeds) after
+/// each chunk of seeds.
+pub fn scan_seeds<T, F, P>(
+    level: &LevelData,
+    seeds: RangeInclusive<i32>,
+    threads: usize,
+    filter: F,
+    progress: P,
+) -> Vec<T>
+where
+    T: Send,
+    F: Fn(i32, Vec<OutputSeedIndexer>) -> Option<T> + Sync,
+    P: Fn(u64, u64) + Sync,
+{
+    let (start, end) = (*seeds.start() as i64, *seeds.end() as i64);
+    if start > end {
+        return Vec::new();
+    }
+
+    let total = (end - start + 1) as u64;
+    let next = AtomicI64::new(start);
+    let done = AtomicU64::new(0);
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| {
+                let mut found = Vec::new();
+
+                loop {
+                    let chunk_start = next.fetch_add(CHUNK_SIZE, Ordering::Relaxed);
+                    if chunk_start > end {
+                        break;
+                    }
+                    let chunk_end = (chunk_start + CHUNK_SIZE - 1).min(end);
+
+                    for seed in chunk_start..=chunk_end {
+                        let seed = seed as i32;
+                        let mut layout = Vec::new();
+                        level.take(&mut UnityRandom::from(seed), &mut layout);
+
+                        if let Some(v) = filter(seed, layout) {
+                            found.push((seed, v));
+                        }
+                    }
+
+                    let count = (chunk_end - chunk_start + 1) as u64;
+                    progress(done.fetch_add(count, Ordering::Relaxed) + count, total);
+                }
+
+                if let Ok(mut results) = results.lock() {
+                    results.extend(found);
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_default();
+    results.sort_by_key(|(seed, _)| *seed);
+    results.into_iter().map(|(_, v)| v).collect()
+}
+
+pub struct SeedFinder<'a> {
+    level: &'a LevelData,
+    constraints: Vec<SeedConstraint>,
+    threads: usize,
+    /// whether seeds where some constraints could not be checked are found too
+    include_unknown: bool,
+}
+
+impl<'a> SeedFinder<'a> {
+    pub fn new(level: &'a LevelData, constraints: Vec<SeedConstraint>) -> Self {
+        Self {
+            level,
+            constraints,
+            threads: thread::available_parallelism()
+                .map(|v| v.get())
+                .unwrap_or(1),
+            include_unknown: false,
+        }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// also finds the seeds where no constraint failed but some could not be checked
+    pub fn with_unknown(mut self, include_unknown: bool) -> Self {
+        self.include_unknown = include_unknown;
+        self
+    }
+
+    /// `Failed` if any constraint failed, otherwise `Unknown` if any could not be checked
+    pub fn matches(&self, layout: &[OutputSeedIndexer]) -> ConstraintCheck {
+        self.constraints
+            .iter()
+            .map(|c| c.check(layout))
+            .fold(ConstraintCheck::Matched, |result, check| match (result, check) {
+                (ConstraintCheck::Failed, _) | (_, ConstraintCheck::Failed) => ConstraintCheck::Failed,
+                (ConstraintCheck::Unknown, _) | (_, ConstraintCheck::Unknown) => ConstraintCheck::Unknown,
+                _ => ConstraintCheck::Matched,
+            })
+    }
+
+    /// gives every seed in the range whose layout respects all constraints to
+    /// `on_match` as soon as it is found, from the thread that found it, so the
+    /// seeds don't come in order. Nothing is kept once `on_match` returns.
+    pub fn find(
+        &self,
+        seeds: RangeInclusive<i32>,
+        on_match: impl Fn(SeedMatch) + Sync,
+        progress: impl Fn(u64, u64) + Sync,
+    ) {
+        scan_seeds(
+            self.level,
+            seeds,
+            self.threads,
+            |seed, layout| {
+                match self.matches(&layout) {
+                    ConstraintCheck::Failed => {}
+                    ConstraintCheck::Unknown if !self.include_unknown => {}
+                    check => on_match(SeedMatch { seed, check, layout }),
+                }
+                None::<()>
+            },
+            progress,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+
+    use glr_core::seed_indexer_result::Provenance;
+
+    use super::*;
+    use crate::seed_gen::test_levels;
+
+    fn key(item: &str, zone: i32, id: i32) -> OutputSeedIndexer {
+        OutputSeedIndexer::Key(ItemKind::from(item), 0, zone, id, Provenance::default())
+    }
+
+    #[test]
+    fn test_parse_constraints() {
+        assert_eq!(
+            "zone:KeyZ51:51:3".parse(),
+            Ok(SeedConstraint::ItemInZone { item: ItemKind::ColoredKey { for_zone: 51 }, zone: 51, max_id: Some(3) })
+        );
+        assert_eq!("unlocked:Cell".parse(), Ok(SeedConstraint::ItemUnlocked { item: ItemKind::Cell, nth: 0 }));
+        assert_eq!("overflow:0".parse(), Ok(SeedConstraint::MaxOverflow(0)));
+
+        // values that don't fit the field are rejected instead of wrapping
+        assert!("zone:KeyZ51:4294967347".parse::<SeedConstraint>().is_err());
+        assert!("unlocked:Cell:-1".parse::<SeedConstraint>().is_err());
+        assert!("overflow:-1".parse::<SeedConstraint>().is_err());
+        assert!("zone:KeyZ51".parse::<SeedConstraint>().is_err());
+    }
+
+    #[test]
+    fn test_unlocked() {
+        let unlocked = |nth| SeedConstraint::ItemUnlocked { item: ItemKind::Cell, nth };
+        let layout = [
+            key("Cell", 51, 1),
+            OutputSeedIndexer::Seed(0.5),
+            OutputSeedIndexer::LockStateChange(0, 51, 1, LockState::HackLock),
+            key("Cell", 52, 2),
+            key("Cell", 53, 3),
+            OutputSeedIndexer::LockStateChange(0, 53, 3, LockState::Unlocked),
+        ];
+
+        // the lock state doesn't have to be next to the item
+        assert_eq!(unlocked(0).check(&layout), ConstraintCheck::Failed);
+        assert_eq!(unlocked(1).check(&layout), ConstraintCheck::Unknown);
+        assert_eq!(unlocked(2).check(&layout), ConstraintCheck::Matched);
+        assert_eq!(unlocked(3).check(&layout), ConstraintCheck::Failed);
+
+        let levels = test_levels();
+        let finder = SeedFinder::new(&levels["R1A1"], vec![unlocked(1), SeedConstraint::MaxOverflow(0)]);
+        assert_eq!(finder.matches(&layout), ConstraintCheck::Unknown);
+        let finder = SeedFinder::new(&levels["R1A1"], vec![unlocked(1), unlocked(0)]);
+        assert_eq!(finder.matches(&layout), ConstraintCheck::Failed);
+    }
+
+    #[test]
+    fn test_scan_seeds() {
+        let levels = test_levels();
+        let level = &levels["R1A1"];
+        let seeds = -300..=300;
+
+        let expected: Vec<(i32, usize)> = seeds
+            .clone()
+            .map(|seed| {
+                let mut layout = Vec::new();
+                level.take(&mut UnityRandom::from(seed), &mut layout);
+                (seed, layout.len())
+            })
+            .filter(|(seed, _)| seed % 7 == 0)
+            .collect();
+
+        let last_progress = AtomicU64::new(0);
+        let found = scan_seeds(
+            level,
+            seeds,
+            4,
+            |seed, layout| (seed % 7 == 0).then_some((seed, layout.len())),
+            |done, total| {
+                assert_eq!(total, 601);
+                last_progress.fetch_max(done, Ordering::Relaxed);
+            },
+        );
+
+        // the results are in seed order whatever thread found them
+        assert_eq!(found, expected);
+        assert_eq!(last_progress.into_inner(), 601);
+        assert!(scan_seeds(level, RangeInclusive::new(5, 4), 4, |seed, _| Some(seed), |_, _| ()).is_empty());
+    }
+
+    #[test]
+    fn test_find_unknown() {
+        let levels = test_levels();
+        let level = &levels["R1A1"];
+        // the HSU of R1A1 is not in a container so it usually has no lock state
+        let finder = || SeedFinder::new(level, vec!["unlocked:HSU_FindTakeSample".parse().unwrap()]).with_threads(2);
+        let find = |finder: SeedFinder| {
+            let found = Mutex::new(Vec::new());
+            finder.find(0..=20, |m| found.lock().unwrap().push((m.seed, m.check)), |_, _| ());
+            let mut found = found.into_inner().unwrap();
+            found.sort_by_key(|(seed, _)| *seed);
+            found
+        };
+
+        let checks: Vec<(i32, ConstraintCheck)> = (0..=20)
+            .map(|seed| {
+                let mut layout = Vec::new();
+                level.take(&mut UnityRandom::from(seed), &mut layout);
+                (seed, finder().matches(&layout))
+            })
+            .collect();
+        assert!(checks.iter().any(|(_, check)| *check == ConstraintCheck::Unknown));
+
+        // seeds that could not be fully checked are only found when asked for
+        let matched: Vec<_> = checks.iter().filter(|(_, check)| *check == ConstraintCheck::Matched).cloned().collect();
+        let not_failed: Vec<_> = checks.iter().filter(|(_, check)| *check != ConstraintCheck::Failed).cloned().collect();
+        assert_eq!(find(finder()), matched);
+        assert_eq!(find(finder().with_unknown(true)), not_failed);
+    }
+}
@@ -1,7 +1,13 @@
 use std::num::Wrapping;
 
 const MT19937: Wrapping<u32> = Wrapping(1812433253);
+/// `value` multiplies by this reciprocal instead of dividing by 8388607, both give the same floats
+const VALUE_SCALE: f32 = 1f32 / 8388607f32;
 
+/// xorshift128 seeded like unity's `Random.InitState`, the generator the
+/// seed indexer assumes the game uses. The sequences haven't been compared
+/// with ones recorded in unity yet, see the tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SeedGenerator {
     x: Wrapping<u32>,
     y: Wrapping<u32>,
@@ -9,18 +15,57 @@ pub struct SeedGenerator {
     w: Wrapping<u32>,
 }
 
+/// the same as unity's `Random.State`, can be saved and restored with
+/// [`SeedGenerator::get_state`] and [`SeedGenerator::set_state`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedGeneratorState {
+    pub s0: u32,
+    pub s1: u32,
+    pub s2: u32,
+    pub s3: u32,
+}
+
 impl From<i32> for SeedGenerator {
     fn from(value: i32) -> Self {
-        let x = Wrapping(value as u32);
-        let y = MT19937 * x + Wrapping(1);
-        let z = MT19937 * y + Wrapping(1);
-        let w = MT19937 * z + Wrapping(1);
+        let mut generator = SeedGenerator {
+            x: Wrapping(0),
+            y: Wrapping(0),
+            z: Wrapping(0),
+            w: Wrapping(0),
+        };
+        generator.init_state(value);
 
-        SeedGenerator { x, y, z, w }
+        generator
     }
 }
 
 impl SeedGenerator {
+    /// `Random.InitState(seed)`
+    pub fn init_state(&mut self, seed: i32) {
+        self.x = Wrapping(seed as u32);
+        self.y = MT19937 * self.x + Wrapping(1);
+        self.z = MT19937 * self.y + Wrapping(1);
+        self.w = MT19937 * self.z + Wrapping(1);
+    }
+
+    /// `Random.state`
+    pub fn get_state(&self) -> SeedGeneratorState {
+        SeedGeneratorState {
+            s0: self.x.0,
+            s1: self.y.0,
+            s2: self.z.0,
+            s3: self.w.0,
+        }
+    }
+
+    /// `Random.state = state`
+    pub fn set_state(&mut self, state: SeedGeneratorState) {
+        self.x = Wrapping(state.s0);
+        self.y = Wrapping(state.s1);
+        self.z = Wrapping(state.s2);
+        self.w = Wrapping(state.s3);
+    }
+
     /// returns a value from [0 to 2^32 - 1]
     pub fn get_next_u32(&mut self) -> u32 {
         let t = self.x ^ (self.x << 11);
@@ -36,4 +81,109 @@ impl SeedGenerator {
         let u = self.get_next_u32();
         return (u << 9) as f32 / 0xFFFFFFFFu32 as f32;
     }
+
+    /// `Random.value`, returns a value from [0.0 to 1.0] (both inclusive)
+    pub fn value(&mut self) -> f32 {
+        (self.get_next_u32() & 0x7FFFFF) as f32 * VALUE_SCALE
+    }
+
+    /// `Random.Range(int, int)`, returns a value from [min to max).
+    /// If `min > max` the range is (max to min] and if they are equal
+    /// `min` is returned without using a value.
+    pub fn range_i32(&mut self, min: i32, max: i32) -> i32 {
+        match min.cmp(&max) {
+            std::cmp::Ordering::Less => {
+                let len = max.wrapping_sub(min) as u32;
+                min.wrapping_add((self.get_next_u32() % len) as i32)
+            }
+            std::cmp::Ordering::Greater => {
+                let len = min.wrapping_sub(max) as u32;
+                min.wrapping_sub((self.get_next_u32() % len) as i32)
+            }
+            std::cmp::Ordering::Equal => min,
+        }
+    }
+
+    /// `Random.Range(float, float)`, returns a value from [min to max].
+    /// It lerps from max to min so `value() == 0.0` gives `max`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        let t = self.value();
+        t * min + (1f32 - t) * max
+    }
+
+    /// fisher-yates shuffle from the last element to the first, picking
+    /// the swap with `Range(0, i + 1)`. Which shuffles of the game work
+    /// like this hasn't been confirmed, check before modeling one with it.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.range_i32(0, i as i32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // these are not recorded from unity: the values were computed by this implementation and
+    // cross-checked against a separate python port of xorshift128 with unity's InitState.
+    // Replace them with sequences recorded from `Random.InitState` in unity when available.
+
+    #[test]
+    fn test_u32_sequence() {
+        let mut generator = SeedGenerator::from(1234);
+        let values: Vec<u32> = (0..5).map(|_| generator.get_next_u32()).collect();
+        assert_eq!(values, [3463400838, 3496203776, 3452947669, 1278673611, 4169168310]);
+
+        let mut generator = SeedGenerator::from(-5);
+        let values: Vec<u32> = (0..3).map(|_| generator.get_next_u32()).collect();
+        assert_eq!(values, [1539403146, 1789011435, 2181011102]);
+    }
+
+    #[test]
+    fn test_value_and_ranges() {
+        let mut generator = SeedGenerator::from(1234);
+        let values: Vec<f32> = (0..5).map(|_| generator.value()).collect();
+        assert_eq!(values, [0.86955345, 0.7799684, 0.62343854, 0.4297728, 0.0035922532]);
+
+        let mut generator = SeedGenerator::from(1234);
+        let ints: Vec<i32> = (0..8).map(|_| generator.range_i32(0, 10)).collect();
+        assert_eq!(ints, [8, 6, 9, 1, 0, 4, 0, 3]);
+        let ints: Vec<i32> = (0..4).map(|_| generator.range_i32(-5, 5)).collect();
+        assert_eq!(ints, [-3, 2, 3, -5]);
+        let ints: Vec<i32> = (0..4).map(|_| generator.range_i32(10, 3)).collect();
+        assert_eq!(ints, [8, 4, 4, 5]);
+
+        let state = generator.get_state();
+        assert_eq!(generator.range_i32(7, 7), 7);
+        assert_eq!(generator.get_state(), state);
+
+        let mut generator = SeedGenerator::from(1234);
+        let floats: Vec<f32> = (0..4).map(|_| generator.range_f32(2.0, 5.0)).collect();
+        assert_eq!(floats, [2.3913398, 2.6600947, 3.1296844, 3.7106814]);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut generator = SeedGenerator::from(42);
+        let mut items: Vec<i32> = (0..10).collect();
+        generator.shuffle(&mut items);
+
+        assert_eq!(items, [1, 0, 4, 8, 5, 2, 7, 9, 6, 3]);
+    }
+
+    #[test]
+    fn test_state_restore() {
+        let mut generator = SeedGenerator::from(99);
+        generator.get_next_u32();
+        let state = generator.get_state();
+        let first: Vec<u32> = (0..4).map(|_| generator.get_next_u32()).collect();
+
+        generator.init_state(5);
+        generator.set_state(state);
+        let second: Vec<u32> = (0..4).map(|_| generator.get_next_u32()).collect();
+
+        assert_eq!(first, second);
+    }
 }
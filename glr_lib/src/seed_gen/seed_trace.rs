@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+
+use glr_core::seed_indexer_result::{SeedStream, SeedTraceEntry};
+
+use crate::seed_gen::zone_info::zone_identifier::ZoneIdentifier;
+
+thread_local! {
+    static TRACE: RefCell<Option<SeedTrace>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct SeedTrace {
+    level_index: usize,
+    build_index: usize,
+    pending: Vec<(SeedStream, usize, f32)>,
+    entries: Vec<SeedTraceEntry>,
+}
+
+impl SeedTrace {
+    fn push_value(&mut self, stream: SeedStream, value: f32) {
+        let index = match stream {
+            SeedStream::Level => &mut self.level_index,
+            SeedStream::Build => &mut self.build_index,
+        };
+
+        self.pending.push((stream, *index, value));
+        *index += 1;
+    }
+
+    fn flush(&mut self, label: &str, zone: Option<&ZoneIdentifier>, id: Option<i32>) {
+        for (stream, index, value) in self.pending.drain(..) {
+            self.entries.push(SeedTraceEntry {
+                stream,
+                index,
+                value,
+                label: label.to_owned(),
+                layer: zone.map(|z| z.layer_id),
+                dimension: zone.map(|z| z.dimension_id),
+                zone: zone.map(|z| z.zone_id),
+                id,
+            });
+        }
+    }
+}
+
+/// runs `f` while recording every random value taken from a [`TracedIter`]
+/// on this thread and returns the recorded values in the order they were used.
+pub fn record<R>(f: impl FnOnce() -> R) -> (R, Vec<SeedTraceEntry>) {
+    TRACE.with_borrow_mut(|t| *t = Some(SeedTrace::default()));
+
+    let result = f();
+
+    let entries = TRACE
+        .with_borrow_mut(|t| t.take())
+        .map(|mut t| {
+            t.flush("Unlabeled", None, None);
+            t.entries
+        })
+        .unwrap_or_default();
+
+    (result, entries)
+}
+
+/// gives a label to every value taken since the last call.
+/// does nothing if no trace is being recorded.
+pub fn label(label: &str, zone: Option<&ZoneIdentifier>, id: Option<i32>) {
+    TRACE.with_borrow_mut(|t| {
+        if let Some(t) = t {
+            t.flush(label, zone, id);
+        }
+    });
+}
+
+pub struct TracedIter<I> {
+    inner: I,
+    stream: SeedStream,
+}
+
+impl<I> TracedIter<I> {
+    pub fn new(inner: I, stream: SeedStream) -> Self {
+        Self { inner, stream }
+    }
+}
+
+impl<I: Iterator<Item = f32>> Iterator for TracedIter<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.inner.next()?;
+
+        TRACE.with_borrow_mut(|t| {
+            if let Some(t) = t {
+                t.push_value(self.stream, value);
+            }
+        });
+
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glr_core::seed_indexer_result::OutputSeedIndexer;
+
+    use super::*;
+    use crate::seed_gen::{consumers::base_consumer::Consumer, test_levels, unity_random::UnityRandom};
+
+    #[test]
+    fn test_trace_matches_take() {
+        let levels = test_levels();
+        let level = &levels["R1A1"];
+
+        let mut layout: Vec<OutputSeedIndexer> = Vec::new();
+        level.take(&mut UnityRandom::from(1), &mut layout);
+
+        let mut traced_layout: Vec<OutputSeedIndexer> = Vec::new();
+        let mut seed_iter = TracedIter::new(UnityRandom::from(1), SeedStream::Level);
+        let (_, entries) = record(|| level.take(&mut seed_iter, &mut traced_layout));
+
+        // tracing doesn't change what is generated
+        assert_eq!(format!("{traced_layout:?}"), format!("{layout:?}"));
+
+        // every level seed taken is in the trace once, in order, with its value
+        let level_entries: Vec<_> = entries.iter().filter(|e| e.stream == SeedStream::Level).collect();
+        let expected: Vec<(usize, f32)> = UnityRandom::from(1).take(level_entries.len()).enumerate().collect();
+        assert_eq!(level_entries.iter().map(|e| (e.index, e.value)).collect::<Vec<_>>(), expected);
+
+        assert!(level_entries[..level.skip_start].iter().all(|e| e.label == "Skip start"));
+        assert_ne!(level_entries[level.skip_start].label, "Skip start");
+
+        // the seeds that placed each item are labeled with its zone and id
+        for v in &layout {
+            let (zone, id) = match v {
+                OutputSeedIndexer::Key(_, _, zone, id, _) => (*zone, *id),
+                OutputSeedIndexer::ResourcePack(_, _, zone, id, ..) => (*zone, *id),
+                _ => continue,
+            };
+
+            assert!(
+                level_entries.iter().any(|e| e.zone == Some(zone) && e.id == Some(id) && e.label != "Unlabeled"),
+                "{v:?}"
+            );
+        }
+    }
+}
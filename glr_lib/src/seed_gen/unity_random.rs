@@ -1,5 +1,9 @@
 use crate::seed_gen::seed_generator::SeedGenerator;
 
+/// the seeds the game pre-generates for a level: 1024 values of
+/// [`SeedGenerator::get_next_f32`] (`(u << 9) / 0xFFFFFFFF`, not `Random.value`)
+/// scaled by 0.9999 that are reused in a loop once they run out.
+/// For the rest of unity's random api use [`SeedGenerator`] directly.
 pub struct UnityRandom {
     current_id: usize,
     generator: Option<SeedGenerator>,
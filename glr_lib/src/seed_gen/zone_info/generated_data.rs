@@ -2,7 +2,7 @@
 use glr_core::seed_indexer_result::LockState;
 use serde::{Deserialize, Serialize};
 
-use crate::seed_gen::{marker_set::MarkerSetHash, zone_info::{
+use crate::seed_gen::{marker_set::MarkerSetHash, seed_trace, zone_info::{
     unlock_method::ZoneLocationSpawn,
     zone_data::{RoomSize, ZoneData},
     zone_identifier::ZoneIdentifier,
@@ -51,21 +51,28 @@ impl GeneratedZone {
             &zone_data.rooms,
             zone_data.allow_containers_alloc,
         );
+        seed_trace::label("Container alloc", Some(&zone_data.zone_id), None);
+
+        let alloc_small_pickups = Self::create_small_pickups_alloc(
+            build_seeds,
+            &zone_data.rooms,
+            zone_data.allow_small_pickups,
+        );
+        seed_trace::label("Small pickup alloc", Some(&zone_data.zone_id), None);
+
+        let alloc_big_pickups = Self::create_big_pickups_alloc(
+            build_seeds,
+            &zone_data.rooms,
+            zone_data.allow_big_pickups,
+        );
+        seed_trace::label("Big pickup alloc", Some(&zone_data.zone_id), None);
         
         Self {
             zone_id: zone_data.zone_id.clone(),
             alloc_containers,
             default_lock,
-            alloc_small_pickups: Self::create_small_pickups_alloc(
-                build_seeds,
-                &zone_data.rooms,
-                zone_data.allow_small_pickups,
-            ),
-            alloc_big_pickups: Self::create_big_pickups_alloc(
-                build_seeds,
-                &zone_data.rooms,
-                zone_data.allow_big_pickups,
-            ),
+            alloc_small_pickups,
+            alloc_big_pickups,
             last_overflow_lock: None,
             alloc_terminals: Self::initial_allocations_from_vec(&zone_data.terminals, 1),
             alloc_other: Self::initial_allocations_from_vec(&zone_data.alloc_other, 1),
@@ -220,7 +227,7 @@ impl GeneratedZone {
         alloc_type: &AllocType,
         seed_iter: &mut dyn Iterator<Item = f32>,
         build_seeds: &mut impl Iterator<Item = f32>,
-        _debug_str: Option<&str>,
+        debug_str: Option<&str>,
         _check_alloc: bool,
     ) -> isize {
         // disabled for now as ALL of this is just testing
@@ -266,6 +273,7 @@ impl GeneratedZone {
                 AllocType::Terminal => None,
                 AllocType::Other => None,
             };
+            seed_trace::label(debug_str.unwrap_or("Spawn"), Some(&self.zone_id), Some(-1));
             
             return -1;
         }
@@ -273,7 +281,7 @@ impl GeneratedZone {
         let values_per_room = Self::calculate_values_per_room(&spawns_per_room, weights);
 
         // #[cfg(debug_assertions)]
-        // match _debug_str {
+        // match debug_str {
         //     Some(s) => println!("s: {} from {} in {}", seed, s, self.zone_id.zone_id),
         //     None => println!("s: {}", seed),
         // }
@@ -317,7 +325,10 @@ impl GeneratedZone {
                 v.retain(|(_, v)| *v > 0);
         });
 
-        id.unwrap_or_default() as isize
+        let id = id.unwrap_or_default();
+        seed_trace::label(debug_str.unwrap_or("Spawn"), Some(&self.zone_id), Some(id as i32));
+
+        id as isize
     }
 
     fn get_room(seed: f32, values_per_room: &Vec<f32>) -> usize {
@@ -434,6 +445,7 @@ pub fn grab_spawn_id(
         Some(id)
     } else {
         let _ = seed_iter.next();
+        seed_trace::label(debug_str.unwrap_or("Spawn"), Some(&spawn.zone_id), None);
 
         None
     }
@@ -1,14 +1,17 @@
-use glr_core::seed_indexer_result::{OutputSeedIndexer, ResourceType};
+use glr_core::seed_indexer_result::{
+    Confidence, ItemKind, LockState, OutputSeedIndexer, Provenance, ResourceTotal, ResourceType, SeedStream,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     output_trait::OutputTrait,
     seed_gen::{
-            consumers::base_consumer::Consumer, marker_set::MarkerSetHash, unity_random::UnityRandom, zone_info::{
+            consumers::base_consumer::Consumer, marker_set::MarkerSetHash, seed_trace::{self, TracedIter}, unity_random::UnityRandom, zone_info::{
             generated_data::{AllocType, GeneratedZone, grab_lock_type, grab_spawn_id},
             spawn_object::SpawnObject,
             unlock_method::{UnlockMethodType, ZoneLocationSpawn},
             zone_data::{ContainerOrWorldspawn, ZoneData},
+            zone_identifier::ZoneIdentifier,
         }
     },
 };
@@ -18,7 +21,7 @@ pub const RESOURCE_LOCKED_CHANCE: f32 = 0.8f32;
 pub const CONSUMABLE_UNLOCKED_CHANCE: f32 = 0.85f32;
 pub const OBJECTIVE_UNLOCKED_CHANCE: f32 = 0.75f32;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LevelData {
     pub build_seed: i32,
     pub build_seed_gate_count: usize,
@@ -31,9 +34,78 @@ pub struct LevelData {
     #[serde(default)]
     pub bulk_keys_ovrl: Vec<Vec<ZoneLocationSpawn>>,
     pub staged_objectives: Vec<StagedObjective>,
+    /// the order layers and dimensions are generated in, empty means
+    /// [`GenerationStep::default_order`]
+    #[serde(default)]
+    pub generation_order: Vec<GenerationStep>,
+    /// set for levels that are only partially modeled, everything
+    /// they output is marked as [`Confidence::Inferred`]
+    #[serde(default)]
+    pub inferred: bool,
+    /// zones objectives can roll that aren't described in `zones` (e.g. in
+    /// another dimension). Only the descriptor validator reads them, so it
+    /// doesn't report those objectives.
+    #[serde(default)]
+    pub unmodeled_zones: Vec<ZoneIdentifier>,
+}
+
+/// an overflowed id is wrong no matter how the item got there so it wins over a bugged path
+pub fn provenance(id: isize, is_bugged: bool, rule: String) -> Provenance {
+    let confidence = if id == -1 {
+        Confidence::Overflow
+    } else if is_bugged {
+        Confidence::BuggedPath
+    } else {
+        Confidence::Verified
+    };
+
+    Provenance { confidence, rule }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// marks the items and resources of a partially modeled level as inferred
+struct InferredOutput<'a, O>(&'a mut O);
+
+impl<O: OutputTrait<OutputSeedIndexer>> OutputTrait<OutputSeedIndexer> for InferredOutput<'_, O> {
+    fn output(&mut self, mut data: OutputSeedIndexer) {
+        if let OutputSeedIndexer::Key(.., provenance) | OutputSeedIndexer::ResourcePack(.., provenance) = &mut data
+            && provenance.confidence == Confidence::Verified
+        {
+            provenance.confidence = Confidence::Inferred;
+        }
+
+        self.0.output(data);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationStep {
+    pub layer: u8,
+    pub dimension: u8,
+    /// indexes into `staged_objectives` generated after the zones of this step
+    #[serde(default)]
+    pub objectives: Vec<usize>,
+}
+
+impl GenerationStep {
+    /// main, secondary and overload of reality with their objective
+    /// followed by the main layer of dimensions 1 to 19
+    pub fn default_order() -> Vec<GenerationStep> {
+        (0..=2u8)
+            .map(|layer| GenerationStep {
+                layer,
+                dimension: 0,
+                objectives: vec![layer as usize],
+            })
+            .chain((1..20u8).map(|dimension| GenerationStep {
+                layer: 0,
+                dimension,
+                objectives: Vec::new(),
+            }))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StagedObjective {
     pub locations: Vec<Vec<ZoneLocationSpawn>>,
     pub name: String,
@@ -48,6 +120,7 @@ pub struct StagedObjective {
 impl StagedObjective {
     fn get_task<O: OutputTrait<OutputSeedIndexer>>(
         &self,
+        index: usize,
         generated_zones: &mut Vec<GeneratedZone>,
         seed_iter: &mut dyn Iterator<Item = f32>,
         build_seeds: &mut impl Iterator<Item = f32>,
@@ -60,6 +133,7 @@ impl StagedObjective {
             .map(|v| v.iter().map(|v| (self.max_per_zone, v.clone())).collect())
             .collect();
 
+        let rule = format!("staged_objectives[{index}]({})", self.name);
         let mut result = Vec::with_capacity(self.count);
         if self.locations.len() == 0 {
             return vec![];
@@ -79,6 +153,7 @@ impl StagedObjective {
                 return vec![];
             }
             let (count, selected) = &mut choices[rolled_id];
+            seed_trace::label("WardenObjective zone roll", Some(&selected.zone_id), None);
             *count -= 1;
             
             let weights = match self.is_bugged {
@@ -104,13 +179,14 @@ impl StagedObjective {
 
                             output.output(OutputSeedIndexer::Key(
                                 if self.name.as_str() == "CentralGeneratorCluster" {
-                                    "Cell".to_owned()
+                                    ItemKind::Cell
                                 } else {
-                                    self.name.clone()
+                                    ItemKind::from(self.name.as_str())
                                 },
                                 selected.zone_id.dimension_id,
                                 selected.zone_id.zone_id,
                                 id as i32,
+                                provenance(id, self.is_bugged, rule.clone()),
                             ));
                         }
                         None => {}
@@ -131,6 +207,8 @@ impl StagedObjective {
                         alloc_type: sp_t,
                         zone_id: selected.zone_id,
                         skip_before_alloc: self.skip_before_alloc,
+                        rule: rule.clone(),
+                        is_bugged: self.is_bugged,
                     }),
                     None => None,
                 },
@@ -157,7 +235,7 @@ impl LevelData {
         seed_iter: &mut dyn Iterator<Item = f32>,
         overflow_counter: &mut MarkerSetHash,
         output: &mut O,
-    ) -> Option<Vec<ZoneLocationSpawn>> {
+    ) -> Option<Vec<(ZoneLocationSpawn, String)>> {
         let result = self
             .zones
             .iter()
@@ -171,27 +249,34 @@ impl LevelData {
 
                 match v.unlocked_by.unlock_type {
                     UnlockMethodType::None => None,
-                    _ => Some((&v.unlocked_by, v.zone_id.zone_id)),
+                    _ => Some((&v.unlocked_by, v.zone_id)),
                 }
             })
-            .filter_map(|(key, zone_id)| {
-                let (name, useless_seeds) = match key.unlock_type {
-                    UnlockMethodType::None => ("Unknown", 0usize),
+            .filter_map(|(key, key_zone)| {
+                let rule = format!("zone {key_zone} unlocked_by");
+                let (item, useless_seeds) = match key.unlock_type {
+                    UnlockMethodType::None => (ItemKind::Objective("Unknown".to_owned()), 0usize),
                     UnlockMethodType::Cell => {
+                        #[cfg(debug_assertions)]
                         println!("got cell: {:?}", 0..key.placement_count);
                         return Some(
                             (0..key.placement_count)
                                 .into_iter()
-                                .map(|_| key.grab_zone(seed_iter.next().unwrap()))
-                                .collect::<Vec<&ZoneLocationSpawn>>(),
+                                .map(|_| {
+                                    let zone = key.grab_zone(seed_iter.next().unwrap());
+                                    seed_trace::label("Cell zone roll", Some(&zone.zone_id), None);
+                                    (zone.clone(), rule.clone())
+                                })
+                                .collect::<Vec<(ZoneLocationSpawn, String)>>(),
                         );
                     }
-                    UnlockMethodType::ColoredKey => ("Key", 2),
-                    UnlockMethodType::BulkheadKey => ("BulkKey", 1),
+                    UnlockMethodType::ColoredKey => (ItemKind::ColoredKey { for_zone: key_zone.zone_id }, 2),
+                    UnlockMethodType::BulkheadKey => (ItemKind::BulkheadKey { for_zone: Some(key_zone.zone_id) }, 1),
                 };
                 #[cfg(debug_assertions)]
                 println!("Got key");
                 let zone = key.grab_zone(seed_iter.nth(useless_seeds)?);
+                seed_trace::label("Key zone roll", Some(&zone.zone_id), None);
                 let id = grab_spawn_id(
                     generated_zones,
                     zone,
@@ -204,11 +289,13 @@ impl LevelData {
                 )?;
 
                 let _ = build_seeds.next();
+                seed_trace::label("Key build seed", Some(&zone.zone_id), Some(id as i32));
                 output.output(OutputSeedIndexer::Key(
-                    format!("{name}Z{zone_id}"),
+                    item,
                     zone.zone_id.dimension_id,
                     zone.zone_id.zone_id,
                     id as i32,
+                    provenance(id, false, rule),
                 ));
                 output.output(OutputSeedIndexer::LockStateChange(
                     zone.zone_id.dimension_id, 
@@ -221,46 +308,52 @@ impl LevelData {
                 None
             })
             .fold(Vec::new(), |mut v, a| {
-                v.extend(a.into_iter().cloned());
+                v.extend(a);
                 v
             });
 
-        match layer {
-            0 => &self.bulk_keys_main,
-            1 => &self.bulk_keys_sec,
-            _ => &self.bulk_keys_ovrl,
-        }
-        .iter()
-        .filter(|v| v.first().is_some_and(|v| v.zone_id.dimension_id == dim))
-        .for_each(|v| {
-            let zone = &v[(seed_iter.nth(1).unwrap() * v.len() as f32) as usize];
-            let id = grab_spawn_id(
-                generated_zones,
-                zone,
-                AllocType::Container,
-                seed_iter,
-                build_seeds,
-                overflow_counter,
-                Some("BulkKey alloc"),
-                false,
-            )
-            .unwrap_or_default();
+        let (bulk_keys, field) = match layer {
+            0 => (&self.bulk_keys_main, "bulk_keys_main"),
+            1 => (&self.bulk_keys_sec, "bulk_keys_sec"),
+            _ => (&self.bulk_keys_ovrl, "bulk_keys_ovrl"),
+        };
 
-            println!("Got bulk key: layer {} dim {}", layer, dim);
-            output.output(OutputSeedIndexer::Key(
-                "BulkKey".to_owned(),
-                zone.zone_id.dimension_id,
-                zone.zone_id.zone_id,
-                id as i32,
-            ));
-            output.output(OutputSeedIndexer::LockStateChange(
-                zone.zone_id.dimension_id, 
-                zone.zone_id.zone_id, 
-                id as i32, 
-                grab_lock_type(generated_zones, &zone.zone_id, id as i32, true)
-                    .unwrap_or_default()
-            ));
-        });
+        bulk_keys
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.first().is_some_and(|v| v.zone_id.dimension_id == dim))
+            .for_each(|(i, v)| {
+                let zone = &v[(seed_iter.nth(1).unwrap() * v.len() as f32) as usize];
+                seed_trace::label("BulkKey zone roll", Some(&zone.zone_id), None);
+                let id = grab_spawn_id(
+                    generated_zones,
+                    zone,
+                    AllocType::Container,
+                    seed_iter,
+                    build_seeds,
+                    overflow_counter,
+                    Some("BulkKey alloc"),
+                    false,
+                )
+                .unwrap_or_default();
+
+                #[cfg(debug_assertions)]
+                println!("Got bulk key: layer {} dim {}", layer, dim);
+                output.output(OutputSeedIndexer::Key(
+                    ItemKind::BulkheadKey { for_zone: None },
+                    zone.zone_id.dimension_id,
+                    zone.zone_id.zone_id,
+                    id as i32,
+                    provenance(id, false, format!("{field}[{i}]")),
+                ));
+                output.output(OutputSeedIndexer::LockStateChange(
+                    zone.zone_id.dimension_id, 
+                    zone.zone_id.zone_id, 
+                    id as i32, 
+                    grab_lock_type(generated_zones, &zone.zone_id, id as i32, true)
+                        .unwrap_or_default()
+                ));
+            });
 
         Some(result)
     }
@@ -287,21 +380,23 @@ impl LevelData {
         overflow_counter: &mut MarkerSetHash,
         output: &mut O,
         res_type: ResourceType,
-    ) -> Option<()> {
-        let (weights, mut left) = match res_type {
-            ResourceType::Healthpack => (zone.medi_weights, zone.medi),
-            ResourceType::DisinfectPack => (zone.disi_weights, zone.disi),
-            ResourceType::Ammopack => (zone.ammo_weights, zone.ammo * 0.8f32),
-            ResourceType::ToolRefillpack => (zone.tool_weights, zone.tool * 0.7f32),
+    ) -> Option<Vec<(LockState, u8, f32)>> {
+        let (weights, mut left, field) = match res_type {
+            ResourceType::Healthpack => (zone.medi_weights, zone.medi, "medi"),
+            ResourceType::DisinfectPack => (zone.disi_weights, zone.disi, "disi"),
+            ResourceType::Ammopack => (zone.ammo_weights, zone.ammo * 0.8f32, "ammo"),
+            ResourceType::ToolRefillpack => (zone.tool_weights, zone.tool * 0.7f32, "tool"),
         };
 
+        let mut packs = Vec::new();
         if left == 0.0 {
-            return Some(());
+            return Some(packs);
         }
 
         loop {
             let chance_locked_seed = seed_iter.next()?;
             let take_seed = seed_iter.next()?;
+            seed_trace::label("ResourceSpawn lock and size", Some(&zone.zone_id), None);
             // println!("  res seed {_number_seed}");
             let id = grab_spawn_id(
                 generated_zones,
@@ -327,23 +422,23 @@ impl LevelData {
                 Self::try_remove(left, 0.4f32)
             };
 
-            if l <= 0.2f32 && l > 0f32 {
-                output.output(OutputSeedIndexer::ResourcePack(
-                    res_type,
-                    zone.zone_id.dimension_id,
-                    zone.zone_id.zone_id,
-                    id as i32,
-                    pack_size + 1,
-                ));
-            } else {
-                output.output(OutputSeedIndexer::ResourcePack(
-                    res_type,
-                    zone.zone_id.dimension_id,
-                    zone.zone_id.zone_id,
-                    id as i32,
-                    pack_size + 1,
-                ));
-            }
+            // the last pack gets everything that is left, even if it's more or less than it rolled
+            let is_remainder = l <= 0.2f32;
+            let amount = match is_remainder {
+                true => left,
+                false => left - l,
+            };
+
+            output.output(OutputSeedIndexer::ResourcePack(
+                res_type,
+                zone.zone_id.dimension_id,
+                zone.zone_id.zone_id,
+                id as i32,
+                pack_size + 1,
+                amount,
+                is_remainder,
+                provenance(id, false, format!("zone {} {field}", zone.zone_id)),
+            ));
 
             #[cfg(debug_assertions)]
             println!("id: {id}: {:?} from {}", grab_lock_type(
@@ -353,18 +448,20 @@ impl LevelData {
                 chance_locked_seed < RESOURCE_LOCKED_CHANCE
             )
                 .unwrap_or_default(), chance_locked_seed);
+            let lock = grab_lock_type(
+                generated_zones, 
+                &zone.zone_id, 
+                id as i32, 
+                chance_locked_seed < RESOURCE_LOCKED_CHANCE
+            )
+                .unwrap_or_default();
             output.output(OutputSeedIndexer::LockStateChange(
                 zone.zone_id.dimension_id, 
                 zone.zone_id.zone_id, 
                 id as i32, 
-                grab_lock_type(
-                    generated_zones, 
-                    &zone.zone_id, 
-                    id as i32, 
-                    chance_locked_seed < RESOURCE_LOCKED_CHANCE
-                )
-                    .unwrap_or_default()
+                lock
             ));
+            packs.push((lock, pack_size + 1, amount));
 
             left = l;
 
@@ -373,7 +470,7 @@ impl LevelData {
             }
         }
 
-        Some(())
+        Some(packs)
     }
 
     fn do_consumables<O: OutputTrait<OutputSeedIndexer>>(
@@ -393,6 +490,7 @@ impl LevelData {
 
         for _ in 0..zone.artifact_count {
             let build_seed = build_seeds.next().unwrap();
+            seed_trace::label("Artifact container roll", Some(&zone.zone_id), None);
             #[cfg(debug_assertions)]
             println!("Build seed artifact: {build_seed}");
             let val = match build_seed > ARTIFACT_BOX_CHANCE {
@@ -413,21 +511,22 @@ impl LevelData {
             
             // if id == -1 { let _ = seed_iter.next(); }
 
-            let name = match val {
-                ContainerOrWorldspawn::Container => "ArtifactContainer",
-                ContainerOrWorldspawn::Worldspawn => "ArtifactWorldspawn",
+            let item = ItemKind::Artifact {
+                container: val == ContainerOrWorldspawn::Container,
             };
 
             output.output(OutputSeedIndexer::Key(
-                name.to_owned(),
+                item,
                 zone.zone_id.dimension_id,
                 zone.zone_id.zone_id,
                 id as i32,
+                provenance(id, false, format!("zone {} artifact_count", zone.zone_id)),
             ));
         }
 
         for _ in 0..zone.consumable_count {
             let build_seed = build_seeds.next().unwrap();
+            seed_trace::label("Consumable container roll", Some(&zone.zone_id), None);
             #[cfg(debug_assertions)]
             println!("   Build seed consumable: {build_seed}");
             let val = match build_seed > zone.chance_box_consumable {
@@ -446,9 +545,10 @@ impl LevelData {
                 false,
             )?;
 
-            let name = match val {
+            let item = match val {
                 ContainerOrWorldspawn::Container => {
                     let chance_locked_seed = seed_iter.next()?;
+                    seed_trace::label("Consumable lock", Some(&zone.zone_id), Some(id as i32));
                     output.output(OutputSeedIndexer::LockStateChange(
                         zone.zone_id.dimension_id, 
                         zone.zone_id.zone_id, 
@@ -461,16 +561,17 @@ impl LevelData {
                         )
                             .unwrap_or_default()
                     ));
-                    "ConsumableContainer"
+                    ItemKind::Consumable { container: true }
                 }
-                ContainerOrWorldspawn::Worldspawn => "ConsumableWorldspawn",
+                ContainerOrWorldspawn::Worldspawn => ItemKind::Consumable { container: false },
             };
 
             output.output(OutputSeedIndexer::Key(
-                name.to_owned(),
+                item,
                 zone.zone_id.dimension_id,
                 zone.zone_id.zone_id,
                 id as i32,
+                provenance(id, false, format!("zone {} consumable_count", zone.zone_id)),
             ));
         }
 
@@ -485,7 +586,7 @@ impl LevelData {
         overflow_counter: &mut MarkerSetHash,
         output: &mut O,
     ) -> Option<()> {
-        for pickup in &zone.big_pickups {
+        for (i, pickup) in zone.big_pickups.iter().enumerate() {
             let location = ZoneLocationSpawn {
                 zone_id: zone.zone_id,
                 start_weight: pickup.start_weight,
@@ -507,10 +608,11 @@ impl LevelData {
             // println!("Big Pickup in zone got: {id}");
 
             output.output(OutputSeedIndexer::Key(
-                pickup.name.clone(),
+                ItemKind::from(pickup.name.as_str()),
                 zone.zone_id.dimension_id,
                 zone.zone_id.zone_id,
                 id as i32,
+                provenance(id, false, format!("zone {} big_pickups[{i}]", zone.zone_id)),
             ));
         }
 
@@ -525,7 +627,7 @@ impl LevelData {
         overflow_counter: &mut MarkerSetHash,
         output: &mut O,
     ) -> Option<()> {
-        for pickup in &zone.small_pickups {
+        for (i, pickup) in zone.small_pickups.iter().enumerate() {
             let id = grab_spawn_id(
                 generated_zones,
                 &ZoneLocationSpawn {
@@ -543,26 +645,28 @@ impl LevelData {
             )?;
 
             output.output(OutputSeedIndexer::Key(
-                pickup.name.clone(),
+                ItemKind::from(pickup.name.as_str()),
                 zone.zone_id.dimension_id,
                 zone.zone_id.zone_id,
                 id as i32,
+                provenance(id, false, format!("zone {} small_pickups[{i}]", zone.zone_id)),
             ));
         }
 
         Some(())
     }
 
+    /// generates the zones of the layer and dimension of `step`, then its objectives
     fn do_layer<O: OutputTrait<OutputSeedIndexer>>(
         &self,
         generated_zones: &mut Vec<GeneratedZone>,
         build_seeds: &mut impl Iterator<Item = f32>,
-        layer: u8,
-        dim: u8,
+        step: &GenerationStep,
         seed_iter: &mut dyn Iterator<Item = f32>,
         overflow_counter: &mut MarkerSetHash,
         output: &mut O,
     ) -> Option<Vec<SpawnObject>> {
+        let (layer, dim) = (step.layer, step.dimension);
         let cell_iter =
             self.do_layer_keys(generated_zones, build_seeds, layer, dim, seed_iter, overflow_counter, output)?;
 
@@ -583,44 +687,51 @@ impl LevelData {
 
             if before > 0 {
                 build_seeds.nth(before - 1);
+                seed_trace::label("Spawners before", Some(&zone.zone_id), None);
             }
 
-            Self::do_res(
-                generated_zones,
-                zone,
-                seed_iter,
-                build_seeds,
-                overflow_counter,
-                output,
+            let mut totals: Vec<ResourceTotal> = Vec::new();
+            for res_type in [
                 ResourceType::Healthpack,
-            )?;
-            Self::do_res(
-                generated_zones,
-                zone,
-                seed_iter,
-                build_seeds,
-                overflow_counter,
-                output,
                 ResourceType::DisinfectPack,
-            )?;
-            Self::do_res(
-                generated_zones,
-                zone,
-                seed_iter,
-                build_seeds,
-                overflow_counter,
-                output,
                 ResourceType::Ammopack,
-            )?;
-            Self::do_res(
-                generated_zones,
-                zone,
-                seed_iter,
-                build_seeds,
-                overflow_counter,
-                output,
                 ResourceType::ToolRefillpack,
-            )?;
+            ] {
+                let packs = Self::do_res(
+                    generated_zones,
+                    zone,
+                    seed_iter,
+                    build_seeds,
+                    overflow_counter,
+                    output,
+                    res_type,
+                )?;
+
+                for (lock, uses, amount) in packs {
+                    match totals.iter_mut().find(|v| v.resource == res_type && v.lock == lock) {
+                        Some(total) => {
+                            total.packs += 1;
+                            total.uses += uses as u32;
+                            total.amount += amount;
+                        }
+                        None => totals.push(ResourceTotal {
+                            resource: res_type,
+                            lock,
+                            packs: 1,
+                            uses: uses as u32,
+                            amount,
+                        }),
+                    }
+                }
+            }
+
+            if !totals.is_empty() {
+                output.output(OutputSeedIndexer::ZoneResourceSummary(
+                    zone.zone_id.dimension_id,
+                    zone.zone_id.zone_id,
+                    totals,
+                ));
+            }
 
             Self::do_consumables(generated_zones, build_seeds, zone, seed_iter, overflow_counter, output)?;
             Self::do_big_pickus(generated_zones, zone, seed_iter, build_seeds, overflow_counter, output)?;
@@ -628,12 +739,13 @@ impl LevelData {
 
             if after > 0 {
                 build_seeds.nth(after);
+                seed_trace::label("Spawners after", Some(&zone.zone_id), None);
             }
         }
 
         let mut vec: Vec<SpawnObject> = cell_iter
             .into_iter()
-            .map(|v| SpawnObject {
+            .map(|(v, rule)| SpawnObject {
                 name: "Cell".to_owned(),
                 zone_id: v.zone_id,
                 start_weight: v.start_weight,
@@ -641,17 +753,13 @@ impl LevelData {
                 end_weight: v.end_weight,
                 alloc_type: AllocType::BigPickup,
                 skip_before_alloc: 0,
+                rule,
+                is_bugged: false,
             })
             .collect();
 
-        if dim == 0 {
-            if let Some(objective) = self
-                .staged_objectives
-                .get(layer as usize)
-                .map(|v| v.get_task(generated_zones, seed_iter, build_seeds, overflow_counter, output))
-            {
-                vec.extend(objective);
-            }
+        for (index, objective) in step.objectives.iter().filter_map(|id| Some((*id, self.staged_objectives.get(*id)?))) {
+            vec.extend(objective.get_task(index, generated_zones, seed_iter, build_seeds, overflow_counter, output));
         }
 
         Some(vec)
@@ -665,8 +773,21 @@ where
     O: OutputTrait<OutputSeedIndexer>,
 {
     fn take(&self, seed_iter: &mut dyn Iterator<Item = f32>, output: &mut O) {
+        match self.inferred {
+            true => self.generate(seed_iter, &mut InferredOutput(output)),
+            false => self.generate(seed_iter, output),
+        }
+    }
+}
+
+impl LevelData {
+    fn generate<O: OutputTrait<OutputSeedIndexer>>(&self, seed_iter: &mut dyn Iterator<Item = f32>, output: &mut O) {
         // println!("Skipped {} build seeds", self.build_seed_gate_count);
-        let mut build_seeds = UnityRandom::from(self.build_seed).skip(self.build_seed_gate_count);
+        let mut build_seeds = TracedIter::new(UnityRandom::from(self.build_seed), SeedStream::Build);
+        if self.build_seed_gate_count > 0 {
+            let _ = build_seeds.nth(self.build_seed_gate_count - 1);
+            seed_trace::label("Build seed gate", None, None);
+        }
 
         let mut hasher_set_data = MarkerSetHash::default();
         let mut generated_zones: Vec<GeneratedZone> = self
@@ -677,73 +798,44 @@ where
 
         if self.skip_start > 0 {
             let _ = seed_iter.nth(self.skip_start - 1);
+            seed_trace::label("Skip start", None, None);
         }
 
         // for _ in 0..self.skip_start {
         //     println!("seed: {}", seed_iter.next().unwrap());
         // }
 
-        let mut r = vec![
-            self.do_layer(
-                &mut generated_zones,
-                &mut build_seeds,
-                0,
-                0,
-                seed_iter,
-                &mut hasher_set_data,
-                output,
-            ),
-            self.do_layer(
-                &mut generated_zones,
-                &mut build_seeds,
-                1,
-                0,
-                seed_iter,
-                &mut hasher_set_data,
-                output,
-            ),
-            self.do_layer(
-                &mut generated_zones,
-                &mut build_seeds,
-                2,
-                0,
-                seed_iter,
-                &mut hasher_set_data,
-                output,
-            ),
-        ];
-
-        // self.do_layer_cells(&mut generated_zones, 0, seed_iter, output);
-        // self.do_layer_cells(&mut generated_zones, 1, seed_iter, output);
-        // self.do_layer_cells(&mut generated_zones, 2, seed_iter, output);
+        let default_order;
+        let generation_order = match self.generation_order.is_empty() {
+            true => {
+                default_order = GenerationStep::default_order();
+                &default_order
+            }
+            false => &self.generation_order,
+        };
 
-        for dim in 1..20 {
-            r.extend(
+        let mut spawns = Vec::new();
+        for step in generation_order {
+            spawns.extend(
                 self.do_layer(
                     &mut generated_zones,
                     &mut build_seeds,
-                    0,
-                    dim,
+                    step,
                     seed_iter,
                     &mut hasher_set_data,
                     output,
                 )
-                .into_iter()
-                .map(|v| Some(v)),
+                .unwrap_or_default(),
             );
         }
 
-        r.into_iter()
-            .filter_map(|v| v)
-            .map(|v| v.into_iter())
-            .fold(vec![], |mut i, v| {
-                i.extend(v);
-                i
-            })
-            .into_iter()
-            .for_each(|v| {
-                v.take(&mut generated_zones, seed_iter, &mut build_seeds, &mut hasher_set_data, output);
-            });
+        // self.do_layer_cells(&mut generated_zones, 0, seed_iter, output);
+        // self.do_layer_cells(&mut generated_zones, 1, seed_iter, output);
+        // self.do_layer_cells(&mut generated_zones, 2, seed_iter, output);
+
+        for v in spawns {
+            v.take(&mut generated_zones, seed_iter, &mut build_seeds, &mut hasher_set_data, output);
+        }
         
         if hasher_set_data.get_count() > 0 {
             // println!("Overflow: {overflow_counter}");
@@ -752,3 +844,79 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed_gen::test_levels;
+
+    fn confidences(level: &LevelData) -> Vec<Confidence> {
+        let mut layout = Vec::new();
+        level.take(&mut UnityRandom::from(1), &mut layout);
+
+        layout
+            .into_iter()
+            .filter_map(|v| match v {
+                OutputSeedIndexer::Key(.., provenance) | OutputSeedIndexer::ResourcePack(.., provenance) => {
+                    Some(provenance.confidence)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inferred_level() {
+        let levels = test_levels();
+
+        // the levels whose objectives spawn in zones that aren't described, or that aren't finished
+        for name in ["R7C1", "R7C2", "R7D2", "R8C1"] {
+            assert!(levels[name].inferred, "{name}");
+        }
+        let unmodeled = ZoneIdentifier { layer_id: 1, dimension_id: 0, zone_id: 364 };
+        assert_eq!(levels["R8C1"].unmodeled_zones, [unmodeled]);
+        assert!(levels["R1A1"].unmodeled_zones.is_empty());
+
+        let r7c1 = confidences(&levels["R7C1"]);
+        assert!(r7c1.contains(&Confidence::Inferred));
+        assert!(!r7c1.contains(&Confidence::Verified));
+        assert!(!confidences(&levels["R1A1"]).contains(&Confidence::Inferred));
+    }
+
+    /// the layer of every level seed taken while generating `level`, by index
+    fn consumed_layers(level: &LevelData) -> Vec<(usize, Option<u8>)> {
+        let mut seed_iter = TracedIter::new(UnityRandom::from(1), SeedStream::Level);
+        let (_, entries) = seed_trace::record(|| level.take(&mut seed_iter, &mut Vec::new()));
+
+        entries
+            .into_iter()
+            .filter(|e| e.stream == SeedStream::Level)
+            .map(|e| (e.index, e.layer))
+            .collect()
+    }
+
+    #[test]
+    fn test_generation_order() {
+        let levels = test_levels();
+
+        let default = levels["R4A2"].clone();
+        assert!(default.generation_order.is_empty());
+
+        let mut reordered = default.clone();
+        reordered.generation_order = GenerationStep::default_order();
+        reordered.generation_order.swap(0, 1);
+
+        let default_layers = consumed_layers(&default);
+        let reordered_layers = consumed_layers(&reordered);
+        let first_layer = |layers: &[(usize, Option<u8>)]| layers.iter().find_map(|(_, layer)| *layer);
+
+        assert_eq!(first_layer(&default_layers), Some(0));
+        assert_eq!(first_layer(&reordered_layers), Some(1));
+        assert_ne!(default_layers, reordered_layers);
+
+        // spelling out the default order takes the same seeds as leaving it empty
+        let mut explicit = default.clone();
+        explicit.generation_order = GenerationStep::default_order();
+        assert_eq!(consumed_layers(&explicit), default_layers);
+    }
+}
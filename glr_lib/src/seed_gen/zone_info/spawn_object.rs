@@ -1,10 +1,11 @@
-use glr_core::seed_indexer_result::OutputSeedIndexer;
+use glr_core::seed_indexer_result::{ItemKind, OutputSeedIndexer};
 use serde::{Deserialize, Serialize};
 
 use crate::output_trait::OutputTrait;
 use crate::seed_gen::marker_set::MarkerSetHash;
+use crate::seed_gen::seed_trace;
 use crate::seed_gen::zone_info::generated_data::grab_lock_type;
-use crate::seed_gen::zone_info::level_data::OBJECTIVE_UNLOCKED_CHANCE;
+use crate::seed_gen::zone_info::level_data::{OBJECTIVE_UNLOCKED_CHANCE, provenance};
 use crate::{
     seed_gen::zone_info::{
         generated_data::{AllocType, GeneratedZone, grab_spawn_id},
@@ -24,6 +25,9 @@ pub struct SpawnObject {
 
     pub alloc_type: AllocType,
     #[serde(default)] pub skip_before_alloc: usize,
+    /// see [`glr_core::seed_indexer_result::Provenance::rule`]
+    #[serde(default)] pub rule: String,
+    #[serde(default)] pub is_bugged: bool,
 }
 
 impl SpawnObject {
@@ -45,6 +49,7 @@ impl SpawnObject {
         
         if self.skip_before_alloc > 0 {
             let _ = seed_iter.nth(self.skip_before_alloc - 1);
+            seed_trace::label("Skip before alloc", Some(&self.zone_id), None);
         }
         
         let id = grab_spawn_id(
@@ -62,12 +67,14 @@ impl SpawnObject {
             true => Some(seed_iter.next().unwrap()),
             false => None,
         };
+        seed_trace::label("SpawnObject lock", Some(&self.zone_id), Some(id as i32));
 
         output.output(OutputSeedIndexer::Key(
-            self.name,
+            ItemKind::from(self.name.as_str()),
             self.zone_id.dimension_id,
             self.zone_id.zone_id,
             id as i32,
+            provenance(id, self.is_bugged, self.rule),
         ));
         if let Some(locked_seed) = locked_seed {
             output.output(OutputSeedIndexer::LockStateChange(
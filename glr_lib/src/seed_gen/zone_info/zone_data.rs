@@ -5,7 +5,7 @@ use crate::seed_gen::zone_info::{
     zone_obj_spawn::ZoneObjectSpawn,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoneData {
     pub zone_id: ZoneIdentifier,
     pub unlocked_by: UnlockMethod,
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, Hash, Serialize, Deserialize, PartialEq, Eq)]
@@ -6,3 +8,9 @@ pub struct ZoneIdentifier {
     pub dimension_id: u8,
     pub zone_id: i32,
 }
+
+impl Display for ZoneIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "L{} D{} Z{}", self.layer_id, self.dimension_id, self.zone_id)
+    }
+}
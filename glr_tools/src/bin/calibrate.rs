@@ -0,0 +1,134 @@
+use std::{env, io::Write, ops::RangeInclusive, path::PathBuf, process::ExitCode, thread};
+
+use glr_lib::{
+    dll_exports::functions::load_level_overrides,
+    seed_gen::{
+        calibration::{CalibrationCandidate, ObservedRun, calibrate, unmatched},
+        levels::LevelDescriptors,
+    },
+};
+
+const USAGE: &str = "usage: calibrate <LEVEL> <LOG>... [--skip MIN-MAX] [--gate MIN-MAX] [--threads N] [--descriptors PATH]
+
+Finds the skip_start and build_seed_gate_count of LEVEL that make the seed indexer
+reproduce the keys and objective items found in the logs. Prints every value pair
+that reproduces the most of them and the items the first one still misses.
+
+    --skip MIN-MAX       skip_start values to try (0-10 by default)
+    --gate MIN-MAX       build_seed_gate_count values to try (0-1200 by default)
+    --descriptors PATH   load LEVEL from this descriptors json instead of the included ones
+
+example: calibrate R1A1 logs/*.txt --gate 300-350";
+
+const MAX_PRINTED: usize = 20;
+
+fn parse_range(value: Option<String>) -> Option<RangeInclusive<usize>> {
+    let value = value?;
+    let (min, max) = value.split_once('-')?;
+
+    Some(min.parse().ok()?..=max.parse().ok()?)
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut threads = thread::available_parallelism()
+        .map(|v| v.get())
+        .unwrap_or(1);
+    let mut skip = 0..=10;
+    let mut gate = 0..=1200;
+    let mut descriptors_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--skip" => skip = parse_range(args.next()).unwrap_or(skip),
+            "--gate" => gate = parse_range(args.next()).unwrap_or(gate),
+            "--threads" => {
+                threads = args.next().and_then(|v| v.parse().ok()).unwrap_or(threads)
+            }
+            "--descriptors" => descriptors_path = args.next(),
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() < 2 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(path) = descriptors_path {
+        if let Err(e) = load_level_overrides(PathBuf::from(&path)) {
+            eprintln!("Failed to load {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let level_name = positional[0].to_ascii_uppercase();
+    let descriptors = LevelDescriptors::default();
+    let Some(level) = descriptors.get_level_by_name(&level_name) else {
+        eprintln!("Level {level_name} is not in the level descriptors");
+        return ExitCode::FAILURE;
+    };
+
+    let paths: Vec<PathBuf> = positional[1..].iter().map(PathBuf::from).collect();
+    let runs: Vec<ObservedRun> = ObservedRun::from_logs(&paths)
+        .into_iter()
+        .filter(|run| run.level.to_string() == level_name)
+        .collect();
+
+    if runs.is_empty() {
+        eprintln!("The logs have no runs of {level_name} with keys or objective items");
+        return ExitCode::FAILURE;
+    }
+    eprintln!(
+        "Found {} runs of {level_name} with {} items",
+        runs.len(),
+        runs.iter().map(|run| run.locations.len()).sum::<usize>()
+    );
+
+    let result = calibrate(&level, &runs, skip, gate, threads, |done, total| {
+        eprint!("\rTried {done}/{total} values");
+        let _ = std::io::stderr().flush();
+    });
+    eprintln!();
+
+    match result.is_exact() {
+        true => println!("{} value pairs reproduce all {} items:", result.best.len(), result.total),
+        false => println!(
+            "No exact match, {} value pairs reproduce {} of {} items:",
+            result.best.len(),
+            result.matched,
+            result.total
+        ),
+    }
+    for candidate in result.best.iter().take(MAX_PRINTED) {
+        println!("  skip_start {} build_seed_gate_count {}", candidate.skip_start, candidate.build_seed_gate_count);
+    }
+    if result.best.len() > MAX_PRINTED {
+        println!("  ... and {} more, logs with more keys or objective items can narrow it down", result.best.len() - MAX_PRINTED);
+    }
+
+    let current = CalibrationCandidate {
+        skip_start: level.skip_start,
+        build_seed_gate_count: level.build_seed_gate_count,
+    };
+    if !result.best.contains(&current) {
+        println!(
+            "The current values (skip_start {} build_seed_gate_count {}) are not among them",
+            current.skip_start, current.build_seed_gate_count
+        );
+    }
+
+    if let Some(first) = result.best.first().filter(|_| !result.is_exact()) {
+        println!("Items the first pair misses:");
+        for (seed, location) in unmatched(&level, &runs, *first) {
+            println!("  seed {seed}: {location}");
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,86 @@
+use std::{collections::BTreeMap, env, path::Path, process::ExitCode};
+
+use glr_lib::seed_gen::datablock_skeleton::{Datablocks, LayerSource, SkeletonSource, build_skeleton};
+
+const USAGE: &str = "usage: datablock_skeleton <DUMP_DIR> <LEVEL> --layout ID [OPTIONS]
+
+Builds a level_descriptors.json entry for LEVEL out of the game's datablock dumps in
+DUMP_DIR (GameData_LevelLayoutDataBlock_bin.json, ..._ExpeditionBalanceDataBlock_bin.json,
+..._WardenObjectiveDataBlock_bin.json and optionally ..._ConsumableDistributionDataBlock_bin.json).
+Everything that can't be read from the datablocks is listed under \"todo\".
+
+    --layout ID                 LevelLayout of the main layer
+    --objective ID              WardenObjective of the main layer
+    --secondary ID              LevelLayout of the secondary layer
+    --secondary-objective ID    WardenObjective of the secondary layer
+    --overload ID               LevelLayout of the overload layer
+    --overload-objective ID     WardenObjective of the overload layer
+    --balance ID                ExpeditionBalance of the expedition
+    --build-seed SEED           BuildSeed of the expedition
+
+example: datablock_skeleton dumps R1A1 --layout 1 --objective 1 --balance 1 --build-seed 333";
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut source = SkeletonSource::default();
+    let mut layouts = [None; 3];
+    let mut objectives = [None; 3];
+
+    while let Some(arg) = args.next() {
+        let mut id = || args.next().and_then(|v| v.parse::<u64>().ok());
+        match arg.as_str() {
+            "--layout" => layouts[0] = id(),
+            "--secondary" => layouts[1] = id(),
+            "--overload" => layouts[2] = id(),
+            "--objective" => objectives[0] = id(),
+            "--secondary-objective" => objectives[1] = id(),
+            "--overload-objective" => objectives[2] = id(),
+            "--balance" => source.balance = id(),
+            "--build-seed" => source.build_seed = args.next().and_then(|v| v.parse().ok()),
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() != 2 || layouts[0].is_none() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    for layer in 0..3 {
+        source.layers[layer] = layouts[layer].map(|layout| LayerSource {
+            layout,
+            objective: objectives[layer],
+        });
+    }
+
+    let blocks = match Datablocks::load_dir(Path::new(&positional[0])) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            eprintln!("Failed to load the datablocks: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let skeleton = match build_skeleton(&blocks, &source) {
+        Ok(skeleton) => skeleton,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let level = BTreeMap::from([(positional[1].as_str(), &skeleton)]);
+    println!("{}", serde_json::to_string_pretty(&level).unwrap_or_default());
+
+    eprintln!("{} fields still need to be filled in:", skeleton.todo.len());
+    for todo in &skeleton.todo {
+        eprintln!("  {todo}");
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,150 @@
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use glr_core::{seed_indexer_result::OutputSeedIndexer, token::Token};
+use glr_lib::{
+    dll_exports::{functions::process_paths_collect, token_parsers::token_parser_base::TokenParserBase},
+    seed_gen::{
+        consumers::base_consumer::Consumer,
+        layout_variants::{LAYOUT_VARIANTS, LayoutVariant, LayoutVariants, hash_to_hex},
+        levels::LevelDescriptors,
+        unity_random::UnityRandom,
+    },
+};
+
+const USAGE: &str = "usage:
+    layout_variants scan <LOG>...
+        prints the overflow hash of every level played in the logs and the variant it is known as
+    layout_variants add <LEVEL> <HASH> <NAME> [NOTES] [--file PATH] [--unverified]
+        names the layout variant with that hash in PATH (resources/layout_variants.ron by default).
+        --unverified marks a variant that wasn't checked in game, it is not reported as a known layout";
+
+fn scan(paths: Vec<PathBuf>) -> ExitCode {
+    let descriptors = LevelDescriptors::default();
+    let tokens = process_paths_collect::<TokenParserBase>(paths);
+
+    for token in tokens {
+        let Token::SelectExpedition(level, seed) = token else {
+            continue;
+        };
+        let level_name = level.to_string();
+
+        let Some(level_data) = descriptors.get_level(&level) else {
+            println!("{level_name} seed {seed}: not supported by the seed indexer");
+            continue;
+        };
+
+        let mut layout = Vec::new();
+        level_data.take(&mut UnityRandom::from(seed), &mut layout);
+
+        let overflow = layout.iter().find_map(|v| match v {
+            OutputSeedIndexer::GenerationOverflow(count) => Some(*count),
+            _ => None,
+        });
+        let hash = layout.iter().find_map(|v| match v {
+            OutputSeedIndexer::GenerationOverflowHash(hash) => Some(*hash),
+            _ => None,
+        });
+
+        match (overflow, hash) {
+            (Some(count), Some(hash)) => {
+                let known = LAYOUT_VARIANTS
+                    .as_ref()
+                    .and_then(|v| v.get(&level_name, &hash))
+                    .map(|v| match v.verified {
+                        true => format!("known as {} ({})", v.name, v.notes),
+                        false => format!("candidate {} not verified in game ({})", v.name, v.notes),
+                    })
+                    .unwrap_or("unknown".to_owned());
+
+                println!("{level_name} seed {seed}: overflow {count} hash {} {known}", hash_to_hex(&hash));
+            }
+            _ => println!("{level_name} seed {seed}: no overflow"),
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn add(args: Vec<String>, file: PathBuf, verified: bool) -> ExitCode {
+    let [level, hash, name, rest @ ..] = args.as_slice() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        eprintln!("'{hash}' is not a hash, expected 64 hex chars");
+        return ExitCode::FAILURE;
+    }
+
+    let text = fs::read_to_string(&file).unwrap_or_default();
+    let mut variants: LayoutVariants = match text.trim().is_empty() {
+        true => LayoutVariants::default(),
+        false => match ron::from_str(&text) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Failed to read {}: {e}", file.display());
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let variant = LayoutVariant {
+        name: name.clone(),
+        notes: rest.join(" "),
+        verified,
+    };
+    if let Some(old) = variants.insert(level, hash, variant) {
+        eprintln!("Replaced {} ({})", old.name, old.notes);
+    }
+
+    // keep the explanation at the top of the file
+    let header: String = text
+        .lines()
+        .take_while(|l| l.starts_with("//"))
+        .map(|l| format!("{l}\n"))
+        .collect();
+    let body = match ron::ser::to_string_pretty(&variants, ron::ser::PrettyConfig::default()) {
+        Ok(body) => body,
+        Err(e) => {
+            eprintln!("Failed to serialize the variants: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(e) = fs::write(&file, format!("{header}LayoutVariants{body}\n")) {
+        eprintln!("Failed to write {}: {e}", file.display());
+        return ExitCode::FAILURE;
+    }
+
+    eprintln!("Added {name} to {level} in {}, rebuild to include it", file.display());
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut file = PathBuf::from("resources/layout_variants.ron");
+
+    if let Some(pos) = args.iter().position(|v| v == "--file") {
+        let Some(path) = args.get(pos + 1).cloned() else {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        };
+        file = PathBuf::from(path);
+        args.drain(pos..=pos + 1);
+    }
+    let unverified = args.iter().any(|v| v == "--unverified");
+    args.retain(|v| v != "--unverified");
+
+    match args.first().map(String::as_str) {
+        Some("scan") if args.len() > 1 => scan(args[1..].iter().map(PathBuf::from).collect()),
+        Some("add") => add(args[1..].to_vec(), file, !unverified),
+        Some("--help" | "-h") => {
+            println!("{USAGE}");
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::{env, fs, io::Write, process::ExitCode, thread};
+
+use glr_lib::seed_gen::{
+    consumers::base_consumer::Consumer, layout_stats::LayoutStats, levels::LevelDescriptors,
+    unity_random::UnityRandom,
+};
+
+const USAGE: &str = "usage: level_stats <LEVEL> <FIRST_SEED> <LAST_SEED> [--threads N] [--csv PREFIX] [--compare SEED]
+
+Indexes every seed in the range and outputs, as JSON, how often each item spawns
+in each container, the expected number of locked containers and the resources per zone.
+
+    --csv PREFIX     write PREFIX_items.csv and PREFIX_resources.csv instead of JSON
+    --compare SEED   output how SEED compares to the averages of the level instead
+
+example: level_stats R4E1 0 100000 --compare 1234";
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut threads = thread::available_parallelism()
+        .map(|v| v.get())
+        .unwrap_or(1);
+    let mut csv = None;
+    let mut compare = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                threads = args.next().and_then(|v| v.parse().ok()).unwrap_or(threads)
+            }
+            "--csv" => csv = args.next(),
+            "--compare" => compare = args.next().and_then(|v| v.parse::<i32>().ok()),
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() != 3 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    let (Ok(first), Ok(last)) = (positional[1].parse::<i32>(), positional[2].parse::<i32>()) else {
+        eprintln!("Seeds must be 32 bit integers");
+        return ExitCode::FAILURE;
+    };
+
+    let descriptors = LevelDescriptors::default();
+    let Some(level) = descriptors.get_level_by_name(&positional[0]) else {
+        eprintln!("Level {} is not supported by the seed indexer", positional[0]);
+        return ExitCode::FAILURE;
+    };
+
+    let stats = LayoutStats::collect(&positional[0], &level, first..=last, threads, |done, total| {
+        eprint!("\rIndexed {done}/{total} seeds");
+        let _ = std::io::stderr().flush();
+    });
+    eprintln!();
+
+    if let Some(seed) = compare {
+        let mut layout = Vec::new();
+        level.take(&mut UnityRandom::from(seed), &mut layout);
+
+        println!("{}", serde_json::to_string_pretty(&stats.compare(seed, &layout)).unwrap_or_default());
+        return ExitCode::SUCCESS;
+    }
+
+    match csv {
+        Some(prefix) => {
+            let items = format!("{prefix}_items.csv");
+            let resources = format!("{prefix}_resources.csv");
+
+            if let Err(e) = fs::write(&items, stats.items_csv())
+                .and_then(|_| fs::write(&resources, stats.resources_csv()))
+            {
+                eprintln!("Failed to write csv files: {e}");
+                return ExitCode::FAILURE;
+            }
+
+            eprintln!("Wrote {items} and {resources}");
+        }
+        None => println!("{}", serde_json::to_string_pretty(&stats).unwrap_or_default()),
+    }
+
+    ExitCode::SUCCESS
+}
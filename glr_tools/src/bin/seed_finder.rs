@@ -0,0 +1,124 @@
+use std::{
+    env,
+    fmt::Write as _,
+    io::Write,
+    process::ExitCode,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use glr_lib::seed_gen::{
+    levels::LevelDescriptors,
+    seed_finder::{ConstraintCheck, SeedConstraint, SeedFinder},
+};
+
+const USAGE: &str = "usage: seed_finder <LEVEL> <FIRST_SEED> <LAST_SEED> [CONSTRAINT]... [--threads N] [--json] [--unknown]
+
+    --unknown   also prints the seeds where no constraint failed but some could not be checked
+
+constraints:
+    zone:NAME:ZONE[:MAX_ID]   item NAME spawns in ZONE (in a container with id <= MAX_ID)
+    unlocked:NAME[:NTH]       the NTH item called NAME is not locked (NTH starts at 0),
+                              unknown when the item has no lock state
+    overflow:MAX              the level overflows at most MAX times
+
+example: seed_finder R4E1 0 1000000 zone:KeyZ51:51:3 overflow:0";
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut threads = None;
+    let mut json = false;
+    let mut unknown = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => threads = args.next().and_then(|v| v.parse::<usize>().ok()),
+            "--json" => json = true,
+            "--unknown" => unknown = true,
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() < 3 {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    let (Ok(first), Ok(last)) = (positional[1].parse::<i32>(), positional[2].parse::<i32>()) else {
+        eprintln!("Seeds must be 32 bit integers");
+        return ExitCode::FAILURE;
+    };
+
+    let constraints = match positional[3..]
+        .iter()
+        .map(|v| v.parse::<SeedConstraint>())
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let descriptors = LevelDescriptors::default();
+    let Some(level) = descriptors.get_level_by_name(&positional[0]) else {
+        eprintln!("Level {} is not supported by the seed indexer", positional[0]);
+        return ExitCode::FAILURE;
+    };
+
+    let mut finder = SeedFinder::new(&level, constraints).with_unknown(unknown);
+    if let Some(threads) = threads {
+        finder = finder.with_threads(threads);
+    }
+
+    let found = AtomicUsize::new(0);
+    let found_unknown = AtomicUsize::new(0);
+
+    // matches are printed as soon as they are found, so they are not in seed order
+    finder.find(
+        first..=last,
+        |m| {
+            found.fetch_add(1, Ordering::Relaxed);
+            if m.check == ConstraintCheck::Unknown {
+                found_unknown.fetch_add(1, Ordering::Relaxed);
+            }
+
+            // one print per seed so the layouts of different threads don't mix
+            let text = match json {
+                true => serde_json::to_string(&m).unwrap_or_default(),
+                false => {
+                    let mut text = match m.check {
+                        ConstraintCheck::Unknown => format!("Seed {} (some constraints could not be checked):", m.seed),
+                        _ => format!("Seed {}:", m.seed),
+                    };
+                    for item in &m.layout {
+                        let _ = write!(text, "\n    {item:?}");
+                    }
+                    text
+                }
+            };
+            println!("{text}");
+        },
+        |done, total| {
+            eprint!("\rChecked {done}/{total} seeds");
+            let _ = std::io::stderr().flush();
+        },
+    );
+    eprintln!();
+
+    let found = found.into_inner();
+    match unknown {
+        true => eprintln!(
+            "Found {found} matching seeds, {} of them could not be fully checked",
+            found_unknown.into_inner()
+        ),
+        false => eprintln!("Found {found} matching seeds"),
+    }
+
+    ExitCode::SUCCESS
+}
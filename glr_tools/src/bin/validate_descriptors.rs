@@ -0,0 +1,48 @@
+use std::{env, fs, process::ExitCode};
+
+use glr_lib::seed_gen::descriptor_validator::{Severity, validate_descriptors};
+
+const USAGE: &str = "usage: validate_descriptors [PATH]
+
+Checks a level descriptors json (resources/level_descriptors.json by default) for
+zones that don't exist, impossible weights and other mistakes the json format can't catch.
+Exits with a failure if any error is found, warnings are only printed.";
+
+fn main() -> ExitCode {
+    let path = match env::args().nth(1) {
+        Some(arg) if arg == "--help" || arg == "-h" => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Some(path) => path,
+        None => "resources/level_descriptors.json".to_owned(),
+    };
+
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("Failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let issues = match validate_descriptors(&json) {
+        Ok(issues) => issues,
+        Err(e) => {
+            eprintln!("{path} is not a valid descriptors file: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for issue in &issues {
+        println!("{issue}");
+    }
+
+    let errors = issues.iter().filter(|v| v.severity == Severity::Error).count();
+    eprintln!("{} errors, {} warnings", errors, issues.len() - errors);
+
+    match errors {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}